@@ -0,0 +1,270 @@
+use crate::errors::{AppError, Result};
+use crate::models::{KnownField, RuntimeMetadata, KNOWN_EVENTS};
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, Value};
+use blake2::{Blake2b512, Digest};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fmt;
+
+// Highest format two prefix bytes can carry
+const MAX_SS58_FORMAT: u16 = 16383;
+// Format of addresses given as hex, valid on any Substrate chain
+const GENERIC_SS58_FORMAT: u16 = 42;
+const CHECKSUM_PREFIX: &[u8] = b"SS58PRE";
+const CHECKSUM_LEN: usize = 2;
+
+// Development accounts `//Alice` and `//Bob`, used by the mock data
+pub const ALICE: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+pub const BOB: &str = "0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48";
+
+// Account fields of the calls the mock data uses, for chains without runtime metadata.
+// Those of events come from `KNOWN_EVENTS`.
+const WELL_KNOWN_CALL_ACCOUNTS: &[(&str, &str, &[&str])] = &[
+    ("Balances", "transfer_allow_death", &["dest"]),
+    ("Balances", "transfer_keep_alive", &["dest"]),
+    ("Balances", "transfer_all", &["dest"]),
+];
+
+// A 32 byte account id. Parsed from SS58 in any format or from 0x-prefixed hex, rendered
+// as SS58 in `format`. Accounts are stored as hex, see `to_hex`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    account: [u8; 32],
+    format: u16,
+}
+
+impl Address {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+            Some(hex) => Self::from_hex(hex),
+            None => Self::from_ss58(value),
+        }
+    }
+
+    fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = hex::decode(hex).map_err(|e| AppError::InvalidInput(format!("Invalid hex account id: {}", e)))?;
+        let account = <[u8; 32]>::try_from(bytes.as_slice())
+            .map_err(|_| AppError::InvalidInput(format!("Account ids are 32 bytes, got {}", bytes.len())))?;
+        Ok(Self {
+            account,
+            format: GENERIC_SS58_FORMAT,
+        })
+    }
+
+    fn from_ss58(address: &str) -> Result<Self> {
+        let data = bs58::decode(address)
+            .into_vec()
+            .map_err(|e| AppError::InvalidInput(format!("Invalid SS58 address {:?}: {}", address, e)))?;
+        let (format, prefix_len) = match data.first() {
+            Some(&first @ 0..=63) => (u16::from(first), 1),
+            Some(&first @ 64..=127) if data.len() > 1 => {
+                let lower = (first << 2) | (data[1] >> 6);
+                let upper = data[1] & 0b0011_1111;
+                (u16::from(lower) | (u16::from(upper) << 8), 2)
+            }
+            _ => return Err(AppError::InvalidInput(format!("Invalid SS58 address {:?}: unknown prefix", address))),
+        };
+        if data.len() != prefix_len + 32 + CHECKSUM_LEN {
+            return Err(AppError::InvalidInput(format!("SS58 address {:?} does not hold a 32 byte account id", address)));
+        }
+        let (payload, checksum) = data.split_at(prefix_len + 32);
+        if checksum != ss58_checksum(payload) {
+            return Err(AppError::InvalidInput(format!("SS58 address {:?} has a bad checksum", address)));
+        }
+        let mut account = [0; 32];
+        account.copy_from_slice(&payload[prefix_len..]);
+        Ok(Self { account, format })
+    }
+
+    pub fn check_format(format: u16) -> Result<u16> {
+        if format > MAX_SS58_FORMAT {
+            return Err(AppError::InvalidInput(format!("SS58 formats go up to {}, got {}", MAX_SS58_FORMAT, format)));
+        }
+        Ok(format)
+    }
+
+    // Same account, rendered in `format`. Formats must have gone through `check_format`.
+    pub fn with_format(self, format: u16) -> Self {
+        Self { format, ..self }
+    }
+
+    // Lowercase 0x-prefixed hex, how accounts are stored
+    pub fn to_hex(self) -> String {
+        format!("0x{}", hex::encode(self.account))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut data = match self.format {
+            format @ 0..=63 => vec![format as u8],
+            format => vec![
+                ((format & 0b1111_1100) as u8 >> 2) | 0b0100_0000,
+                (format >> 8) as u8 | ((format & 0b0000_0011) as u8) << 6,
+            ],
+        };
+        data.extend_from_slice(&self.account);
+        let checksum = ss58_checksum(&data);
+        data.extend_from_slice(&checksum);
+        f.write_str(&bs58::encode(data).into_string())
+    }
+}
+
+fn ss58_checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = Blake2b512::new().chain_update(CHECKSUM_PREFIX).chain_update(payload).finalize();
+    [hash[0], hash[1]]
+}
+
+// An account id. Accepts SS58 addresses of any network and 0x-prefixed hex public keys,
+// returns SS58 addresses in the chain's format unless a field's `ss58Format` says otherwise.
+#[Scalar(name = "Address")]
+impl ScalarType for Address {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        match &value {
+            Value::String(address) => Address::parse(address).map_err(|e| InputValueError::custom(e.to_string())),
+            _ => Err(InputValueError::expected_type(value)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+// Field of an event or call holding account ids
+#[derive(Debug, Clone)]
+enum FieldPosition {
+    Named(String),
+    // Unnamed fields are stored as an array
+    Index(usize),
+}
+
+// Where account ids sit in event data and call args, by (pallet, variant). Built from the
+// chain's runtime metadata, or from the well-known pallets when there is none.
+#[derive(Debug, Default)]
+pub struct AccountFields {
+    events: HashMap<(String, String), Vec<FieldPosition>>,
+    calls: HashMap<(String, String), Vec<FieldPosition>>,
+}
+
+impl AccountFields {
+    pub fn from_metadata(metadata: &RuntimeMetadata) -> Self {
+        let mut fields = Self::default();
+        for pallet in &metadata.pallets {
+            for event in &pallet.events {
+                let positions = account_positions(event.fields.iter().map(|f| (f.name.as_deref(), f.resolved_type.as_str())));
+                if !positions.is_empty() {
+                    fields.events.insert((pallet.name.clone(), event.name.clone()), positions);
+                }
+            }
+            for call in &pallet.calls {
+                let positions = account_positions(call.fields.iter().map(|f| (f.name.as_deref(), f.resolved_type.as_str())));
+                if !positions.is_empty() {
+                    fields.calls.insert((pallet.name.clone(), call.name.clone()), positions);
+                }
+            }
+        }
+        fields
+    }
+
+    pub fn well_known() -> Self {
+        let events = KNOWN_EVENTS
+            .iter()
+            .map(|event| {
+                let accounts = event.fields.iter().filter(|(_, kind)| *kind == KnownField::Account);
+                let positions: Vec<_> = accounts.map(|(name, _)| FieldPosition::Named(name.to_string())).collect();
+                ((event.pallet_name.to_string(), event.event_name.to_string()), positions)
+            })
+            .filter(|(_, positions)| !positions.is_empty())
+            .collect();
+        let calls = WELL_KNOWN_CALL_ACCOUNTS
+            .iter()
+            .map(|(pallet, name, fields)| {
+                let positions = fields.iter().map(|f| FieldPosition::Named(f.to_string())).collect();
+                ((pallet.to_string(), name.to_string()), positions)
+            })
+            .collect();
+        Self { events, calls }
+    }
+
+    // Event data with its account ids rendered as SS58 addresses of `format`
+    pub fn encode_event(&self, pallet_name: &str, event_name: &str, data: &JsonValue, format: u16) -> JsonValue {
+        encode_fields(self.events.get(&(pallet_name.to_string(), event_name.to_string())), data, format)
+    }
+
+    pub fn encode_call(&self, pallet_name: &str, call_name: &str, args: &JsonValue, format: u16) -> JsonValue {
+        encode_fields(self.calls.get(&(pallet_name.to_string(), call_name.to_string())), args, format)
+    }
+}
+
+fn account_positions<'a>(fields: impl Iterator<Item = (Option<&'a str>, &'a str)>) -> Vec<FieldPosition> {
+    fields
+        .enumerate()
+        .filter(|(_, (_, resolved_type))| holds_accounts(resolved_type))
+        .map(|(index, (name, _))| match name {
+            Some(name) => FieldPosition::Named(name.to_string()),
+            None => FieldPosition::Index(index),
+        })
+        .collect()
+}
+
+// Account ids, possibly in a list or an option, or a `MultiAddress` over them. Some
+// metadata leaves the `MultiAddress` parameters out.
+fn holds_accounts(resolved_type: &str) -> bool {
+    let mut ty = resolved_type;
+    while let Some(inner) = ["Vec<", "Option<"]
+        .iter()
+        .find_map(|wrapper| ty.strip_prefix(wrapper))
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        ty = inner;
+    }
+    ty == "AccountId32" || ty == "MultiAddress" || ty.starts_with("MultiAddress<AccountId32,")
+}
+
+fn encode_fields(positions: Option<&Vec<FieldPosition>>, value: &JsonValue, format: u16) -> JsonValue {
+    let mut value = value.clone();
+    for position in positions.into_iter().flatten() {
+        let field = match (position, &mut value) {
+            (FieldPosition::Named(name), JsonValue::Object(fields)) => fields.get_mut(name),
+            (FieldPosition::Index(index), JsonValue::Array(fields)) => fields.get_mut(*index),
+            _ => None,
+        };
+        if let Some(field) = field {
+            encode_accounts(field, format);
+        }
+    }
+    value
+}
+
+// Re-encode every account id in `value`: plain, in lists and as `MultiAddress::Id`.
+// Anything else is left alone.
+fn encode_accounts(value: &mut JsonValue, format: u16) {
+    match value {
+        JsonValue::String(account) => {
+            if let Ok(address) = Address::parse(account) {
+                *account = address.with_format(format).to_string();
+            }
+        }
+        JsonValue::Array(items) => items.iter_mut().for_each(|item| encode_accounts(item, format)),
+        JsonValue::Object(variant) => {
+            if let Some(id) = variant.get_mut("Id") {
+                encode_accounts(id, format);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Whether `account`, lowercase hex, appears anywhere in `data`. The SQLite `event_accounts`
+// index follows the same rule, so hashes of the same length are indexed too; they just
+// never match an account id.
+pub fn mentions_account(data: &JsonValue, account: &str) -> bool {
+    match data {
+        JsonValue::String(value) => value.eq_ignore_ascii_case(account),
+        JsonValue::Array(items) => items.iter().any(|item| mentions_account(item, account)),
+        JsonValue::Object(fields) => fields.values().any(|field| mentions_account(field, account)),
+        _ => false,
+    }
+}
@@ -0,0 +1,259 @@
+use crate::config::AuthConfig;
+use crate::errors::{AppError, Result};
+use crate::models::{AuthMethod, Caller, RotatedApiKey, Scope};
+use crate::store::{Store, StoredApiKey};
+use actix_web::HttpRequest;
+use async_graphql::{Context, Guard, ID};
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use chrono::Utc;
+use config::ConfigError;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// The credential of an HTTP request, from `Authorization: Bearer <credential>` or
+// `X-Api-Key: <key>`.
+pub fn request_credential(req: &HttpRequest) -> Option<String> {
+    let header = |name| req.headers().get(name).and_then(|value| value.to_str().ok());
+    credential(header("authorization"), header("x-api-key"))
+}
+
+// The credential of a subscription, from the same fields in its `connection_init`
+// payload, e.g. `{"Authorization": "Bearer <credential>"}`. Names are case-insensitive.
+pub fn connection_init_credential(payload: &serde_json::Value) -> Option<String> {
+    let payload = payload.as_object()?;
+    let field = |name: &str| {
+        payload
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_str())
+    };
+    credential(field("authorization"), field("x-api-key"))
+}
+
+fn credential(authorization: Option<&str>, api_key: Option<&str>) -> Option<String> {
+    authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or(api_key)
+        .map(|credential| credential.trim().to_string())
+        .filter(|credential| !credential.is_empty())
+}
+
+struct ConfiguredKey {
+    name: String,
+    key_hash: String,
+    scopes: Vec<Scope>,
+}
+
+// JWT claims, scopes come from `scope` (space separated, as in OAuth) and/or `scopes`.
+// Scopes this service doesn't know are ignored.
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    scopes: Vec<String>,
+}
+
+impl Claims {
+    fn scopes(&self) -> Vec<Scope> {
+        self.scope
+            .iter()
+            .flat_map(|scope| scope.split_whitespace())
+            .chain(self.scopes.iter().map(String::as_str))
+            .filter_map(Scope::parse)
+            .collect()
+    }
+}
+
+struct Settings {
+    required: bool,
+    anonymous_scopes: Vec<Scope>,
+    configured_keys: Vec<ConfiguredKey>,
+    hs256: Option<DecodingKey>,
+    rs256: Option<DecodingKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+// Turns a request's credential into its `Caller`. API keys come from the configuration
+// and the store, a configured key is accepted until `rotate` stored one with its name.
+#[derive(Clone)]
+pub struct Authenticator {
+    store: Arc<dyn Store>,
+    settings: Arc<Settings>,
+}
+
+impl Authenticator {
+    pub fn new(store: Arc<dyn Store>, config: &AuthConfig) -> Result<Self> {
+        let rs256 = match &config.jwt_rs256_public_key_path {
+            Some(path) => {
+                let pem = std::fs::read(path)?;
+                let key = DecodingKey::from_rsa_pem(&pem)
+                    .map_err(|e| ConfigError::Message(format!("Invalid RS256 public key in {}: {}", path, e)))?;
+                Some(key)
+            }
+            None => None,
+        };
+        let settings = Settings {
+            required: config.required,
+            anonymous_scopes: config.anonymous_scopes.clone(),
+            configured_keys: config
+                .api_keys
+                .iter()
+                .map(|k| ConfiguredKey {
+                    name: k.name.clone(),
+                    key_hash: hash_key(&k.key),
+                    scopes: k.scopes.clone(),
+                })
+                .collect(),
+            hs256: config.jwt_hs256_secret.as_ref().map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+            rs256,
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+        };
+        Ok(Self {
+            store,
+            settings: Arc::new(settings),
+        })
+    }
+
+    // Callers without a credential are anonymous, unless credentials are required
+    pub async fn authenticate(&self, credential: Option<&str>) -> Result<Caller> {
+        match credential {
+            None if self.settings.required => Err(AppError::Unauthorized("Credentials are required".to_string())),
+            None => Ok(Caller {
+                subject: "anonymous".to_string(),
+                method: AuthMethod::Anonymous,
+                scopes: self.settings.anonymous_scopes.clone(),
+            }),
+            // API keys are hex, JWTs are dot separated
+            Some(token) if token.contains('.') => self.verify_jwt(token),
+            Some(key) => self.verify_api_key(key).await,
+        }
+    }
+
+    fn verify_jwt(&self, token: &str) -> Result<Caller> {
+        let header = jsonwebtoken::decode_header(token).map_err(invalid_token)?;
+        let key = match header.alg {
+            Algorithm::HS256 => self.settings.hs256.as_ref(),
+            Algorithm::RS256 => self.settings.rs256.as_ref(),
+            _ => None,
+        }
+        .ok_or_else(|| AppError::Unauthorized(format!("{:?} signed tokens are not accepted", header.alg)))?;
+
+        let mut validation = Validation::new(header.alg);
+        if let Some(issuer) = &self.settings.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.settings.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        let claims = jsonwebtoken::decode::<Claims>(token, key, &validation)
+            .map_err(invalid_token)?
+            .claims;
+        Ok(Caller {
+            scopes: claims.scopes(),
+            subject: claims.sub,
+            method: AuthMethod::Jwt,
+        })
+    }
+
+    async fn verify_api_key(&self, key: &str) -> Result<Caller> {
+        let hash = hash_key(key);
+        if let Some(key) = self.store.get_api_key_by_hash(&hash).await? {
+            return Ok(api_key_caller(&key.name, &key.scopes));
+        }
+        let invalid = || AppError::Unauthorized("Invalid API key".to_string());
+        let key = self.settings.configured_keys.iter().find(|k| k.key_hash == hash).ok_or_else(invalid)?;
+        // Revoked once a key of the same name was rotated in
+        if self.store.has_api_key_named(&key.name).await? {
+            return Err(invalid());
+        }
+        Ok(api_key_caller(&key.name, &key.scopes))
+    }
+
+    // Issue a new key named `name` and revoke the others of that name, configured ones included.
+    pub async fn rotate(&self, name: String, scopes: Vec<Scope>) -> Result<RotatedApiKey> {
+        if name.is_empty() || scopes.is_empty() {
+            return Err(AppError::InvalidInput("An API key needs a name and at least one scope".to_string()));
+        }
+        let scopes = scopes.iter().enumerate().filter(|(i, s)| !scopes[..*i].contains(s)).map(|(_, s)| *s).collect();
+        let key = hex::encode(rand::random::<[u8; 32]>());
+        let stored = StoredApiKey {
+            id: ID::from(Uuid::new_v4().to_string()),
+            name,
+            key_hash: hash_key(&key),
+            scopes,
+            created_at: Utc::now(),
+        };
+        self.store.rotate_api_key(stored.clone()).await?;
+        warn!(id = %stored.id.as_str(), name = %stored.name, "Rotated API key, previous keys of that name are revoked");
+        Ok(RotatedApiKey {
+            id: stored.id,
+            name: stored.name,
+            scopes: stored.scopes,
+            key,
+            created_at: stored.created_at,
+        })
+    }
+
+    // Log once at startup who can get in
+    pub async fn log_state(&self) -> Result<()> {
+        let settings = &self.settings;
+        let stored = self.store.list_api_keys().await?;
+        info!(
+            required = settings.required,
+            anonymous_scopes = ?settings.anonymous_scopes,
+            configured_keys = settings.configured_keys.len(),
+            stored_keys = stored.len(),
+            hs256 = settings.hs256.is_some(),
+            rs256 = settings.rs256.is_some(),
+            "Authentication configured"
+        );
+        let admin_key = settings.configured_keys.iter().any(|k| k.scopes.contains(&Scope::Admin))
+            || stored.iter().any(|k| k.scopes.contains(&Scope::Admin));
+        let jwt = settings.hs256.is_some() || settings.rs256.is_some();
+        if !admin_key && !jwt && !settings.anonymous_scopes.contains(&Scope::Admin) {
+            warn!("No admin credentials configured, admin mutations are disabled");
+        }
+        Ok(())
+    }
+}
+
+fn api_key_caller(name: &str, scopes: &[Scope]) -> Caller {
+    Caller {
+        subject: name.to_string(),
+        method: AuthMethod::ApiKey,
+        scopes: scopes.to_vec(),
+    }
+}
+
+fn invalid_token(e: jsonwebtoken::errors::Error) -> AppError {
+    AppError::Unauthorized(format!("Invalid token: {}", e))
+}
+
+fn hash_key(key: &str) -> String {
+    let hash: [u8; 32] = Blake2b::<U32>::digest(key.as_bytes()).into();
+    format!("0x{}", hex::encode(hash))
+}
+
+// Admits callers granted the scope, e.g. `#[graphql(guard = "ScopeGuard(Scope::Read)")]`.
+pub struct ScopeGuard(pub Scope);
+
+impl Guard for ScopeGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        let Some(caller) = ctx.data_opt::<Caller>() else {
+            return Err(AppError::Unauthorized("Credentials are required".to_string()).into());
+        };
+        if caller.has_scope(self.0) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("The {} scope is required", self.0.as_str())).into())
+        }
+    }
+}
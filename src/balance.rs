@@ -0,0 +1,68 @@
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, Value};
+use serde_json::Value as JsonValue;
+use std::fmt;
+
+// An amount in the chain's smallest unit. Balances are u128 on Substrate chains, past what
+// GraphQL's `Int` and JavaScript numbers hold exactly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Balance(pub u128);
+
+impl Balance {
+    // From event data, which holds balances as JSON numbers or, past u64, decimal strings
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        match value {
+            JsonValue::Number(n) => n.as_u64().map(|n| Self(n.into())),
+            JsonValue::String(s) => parse_digits(s),
+            _ => None,
+        }
+    }
+
+    // `self / 10^decimals` in full, trailing zeros of the fraction dropped, then `symbol`
+    // if it is not empty: 1500000000000 with 12 decimals and "DOT" is "1.5 DOT".
+    pub fn format(self, decimals: u8, symbol: &str) -> String {
+        let digits = format!("{:0>width$}", self.0, width = usize::from(decimals) + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - usize::from(decimals));
+        let fraction = fraction.trim_end_matches('0');
+        let mut formatted = if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{}.{}", whole, fraction)
+        };
+        if !symbol.is_empty() {
+            formatted.push(' ');
+            formatted.push_str(symbol);
+        }
+        formatted
+    }
+}
+
+fn parse_digits(s: &str) -> Option<Balance> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok().map(Balance)
+}
+
+impl fmt::Display for Balance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// An unsigned 128-bit integer, serialised as a decimal string. Integers are accepted as
+// input too.
+#[Scalar(name = "Balance")]
+impl ScalarType for Balance {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        let balance = match &value {
+            Value::String(s) => parse_digits(s),
+            Value::Number(n) => n.as_u64().map(|n| Balance(n.into())),
+            _ => return Err(InputValueError::expected_type(value)),
+        };
+        balance.ok_or_else(|| InputValueError::custom("Balances are unsigned 128-bit integers in decimal"))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
@@ -1,7 +1,8 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use config::{Config as ConfigLib, ConfigError, Environment, File};
 use std::net::SocketAddr;
 use once_cell::sync::Lazy;
+use crate::models::Scope;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
@@ -22,10 +23,304 @@ pub struct LoggerConfig {
     pub level: String,
 }
 
+// OpenTelemetry export of the tracing spans, off unless `otlp_endpoint` is set
+#[derive(Debug, Deserialize, Clone)]
+pub struct TelemetryConfig {
+    // OTLP/gRPC collector, e.g. "http://localhost:4317"
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    // Share of traces started here that are exported. Requests carrying a `traceparent`
+    // follow the caller's sampling decision.
+    #[serde(default = "default_sampling_ratio")]
+    pub sampling_ratio: f64,
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+fn default_sampling_ratio() -> f64 {
+    1.0
+}
+
+fn default_service_name() -> String {
+    "chain-metadata-graphql".to_string()
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            sampling_ratio: default_sampling_ratio(),
+            service_name: default_service_name(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Memory,
+    Sqlite,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub backend: StorageBackend,
+    // Only used by the sqlite backend, created on first start
+    #[serde(default = "default_sqlite_path")]
+    pub sqlite_path: String,
+}
+
+fn default_sqlite_path() -> String {
+    "data/chain_metadata.db".to_string()
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::default(),
+            sqlite_path: default_sqlite_path(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct IngestionConfig {
+    // When enabled, events come from the nodes instead of the mock simulator
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_reconnect_delay_secs")]
+    pub reconnect_delay_secs: u64,
+    // /readyz fails while a chain's store is further behind its node's best block
+    #[serde(default = "default_max_lag_blocks")]
+    pub max_lag_blocks: u64,
+}
+
+fn default_reconnect_delay_secs() -> u64 {
+    5
+}
+
+fn default_max_lag_blocks() -> u64 {
+    20
+}
+
+impl Default for IngestionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reconnect_delay_secs: default_reconnect_delay_secs(),
+            max_lag_blocks: default_max_lag_blocks(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct BackfillConfig {
+    // Blocks indexed between two checkpoints
+    #[serde(default = "default_backfill_batch_size")]
+    pub batch_size: u64,
+    // Blocks fetched from the node concurrently within a batch
+    #[serde(default = "default_backfill_parallelism")]
+    pub parallelism: usize,
+    #[serde(default = "default_backfill_retry_delay_secs")]
+    pub retry_delay_secs: u64,
+}
+
+fn default_backfill_batch_size() -> u64 {
+    100
+}
+
+fn default_backfill_parallelism() -> usize {
+    4
+}
+
+fn default_backfill_retry_delay_secs() -> u64 {
+    10
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: default_backfill_batch_size(),
+            parallelism: default_backfill_parallelism(),
+            retry_delay_secs: default_backfill_retry_delay_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LimitsConfig {
+    // Deepest selection nesting a query may have
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    // Most a query may cost, a list field costs its page size times the cost of one entry
+    #[serde(default = "default_max_complexity")]
+    pub max_complexity: usize,
+    // Token bucket per client (API key name or JWT subject, IP address when anonymous): the
+    // operations per second it refills at and how many it holds. 0 turns rate limiting off.
+    #[serde(default = "default_rate_limit_per_sec")]
+    pub rate_limit_per_sec: f64,
+    #[serde(default = "default_rate_limit_burst")]
+    pub rate_limit_burst: u32,
+}
+
+fn default_max_depth() -> usize {
+    12
+}
+
+fn default_max_complexity() -> usize {
+    20_000
+}
+
+fn default_rate_limit_per_sec() -> f64 {
+    10.0
+}
+
+fn default_rate_limit_burst() -> u32 {
+    50
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: default_max_depth(),
+            max_complexity: default_max_complexity(),
+            rate_limit_per_sec: default_rate_limit_per_sec(),
+            rate_limit_burst: default_rate_limit_burst(),
+        }
+    }
+}
+
+// Callers send an API key or a JWT as `Authorization: Bearer <credential>` (or an API
+// key as `X-Api-Key`), subscriptions in their `connection_init` payload.
+#[derive(Deserialize, Clone)]
+pub struct AuthConfig {
+    // Refuse callers without credentials instead of serving them with `anonymous_scopes`
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default = "default_anonymous_scopes")]
+    pub anonymous_scopes: Vec<Scope>,
+    // Accepted until `rotateApiKeys` stores a key under the same name
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    #[serde(default)]
+    pub jwt_hs256_secret: Option<String>,
+    // PEM encoded RSA public key for RS256 tokens
+    #[serde(default)]
+    pub jwt_rs256_public_key_path: Option<String>,
+    // Required `iss` and `aud` claims, when set
+    #[serde(default)]
+    pub jwt_issuer: Option<String>,
+    #[serde(default)]
+    pub jwt_audience: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ApiKeyConfig {
+    pub name: String,
+    pub key: String,
+    pub scopes: Vec<Scope>,
+}
+
+// The config is logged at startup, keep the secrets out of it
+impl std::fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("required", &self.required)
+            .field("anonymous_scopes", &self.anonymous_scopes)
+            .field("api_keys", &self.api_keys)
+            .field("jwt_hs256_secret", &self.jwt_hs256_secret.as_ref().map(|_| "<redacted>"))
+            .field("jwt_rs256_public_key_path", &self.jwt_rs256_public_key_path)
+            .field("jwt_issuer", &self.jwt_issuer)
+            .field("jwt_audience", &self.jwt_audience)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for ApiKeyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyConfig")
+            .field("name", &self.name)
+            .field("key", &"<redacted>")
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+fn default_anonymous_scopes() -> Vec<Scope> {
+    vec![Scope::Read, Scope::Subscribe]
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            required: false,
+            anonymous_scopes: default_anonymous_scopes(),
+            api_keys: Vec::new(),
+            jwt_hs256_secret: None,
+            jwt_rs256_public_key_path: None,
+            jwt_issuer: None,
+            jwt_audience: None,
+        }
+    }
+}
+
+// One indexed chain. The token fields seed `ChainInfo` until the node reports its own.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChainConfig {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_rpc_endpoint")]
+    pub endpoint: String,
+    // Recorded RPC session to serve from a local mock server instead of `endpoint`
+    #[serde(default)]
+    pub replay_file: Option<String>,
+    // Backfill from this block up to the finalized head once ingestion starts. Runs once,
+    // progress is checkpointed in the store and resumed after a restart.
+    #[serde(default)]
+    pub backfill_from_block: Option<u64>,
+    pub ss58_prefix: u16,
+    pub token_symbol: String,
+    pub decimals: u8,
+}
+
+fn default_rpc_endpoint() -> String {
+    "ws://127.0.0.1:9944".to_string()
+}
+
+// Used when the config file has no [[chains]] entries, matches the old single mock chain
+fn default_chains() -> Vec<ChainConfig> {
+    vec![ChainConfig {
+        id: "polkadot-mainnet-mock".to_string(),
+        name: "Polkadot (Mock)".to_string(),
+        endpoint: default_rpc_endpoint(),
+        replay_file: None,
+        backfill_from_block: None,
+        ss58_prefix: 0,
+        token_symbol: "MDOT".to_string(),
+        decimals: 10,
+    }]
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub server: ServerConfig,
     pub logger: LoggerConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)] // Older config files predate the storage section
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub ingestion: IngestionConfig,
+    #[serde(default)]
+    pub backfill: BackfillConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default = "default_chains")]
+    pub chains: Vec<ChainConfig>,
     pub mock_event_min_delay_secs: u64,
     pub mock_event_max_delay_secs: u64,
 }
@@ -46,7 +341,46 @@ impl AppConfig {
             .add_source(Environment::with_prefix("APP").separator("__"))
             .build()?;
 
-        s.try_deserialize()
+        let config: Self = s.try_deserialize()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.chains.is_empty() {
+            return Err(ConfigError::Message("At least one chain must be configured".to_string()));
+        }
+        let mut seen = std::collections::HashSet::new();
+        for chain in &self.chains {
+            if !seen.insert(chain.id.as_str()) {
+                return Err(ConfigError::Message(format!("Duplicate chain id: {}", chain.id)));
+            }
+        }
+        // Dotted credentials are verified as JWTs, such a key could never be used
+        if let Some(key) = self.auth.api_keys.iter().find(|k| k.key.contains('.')) {
+            return Err(ConfigError::Message(format!("API key {} must not contain '.'", key.name)));
+        }
+        Ok(())
+    }
+}
+
+// Every section at its defaults, indexing `chains`
+#[cfg(test)]
+impl AppConfig {
+    pub fn for_chains(chains: Vec<ChainConfig>) -> Self {
+        Self {
+            server: ServerConfig { host: "127.0.0.1".to_string(), port: 0 },
+            logger: LoggerConfig { level: "info".to_string() },
+            telemetry: TelemetryConfig::default(),
+            storage: StorageConfig::default(),
+            ingestion: IngestionConfig::default(),
+            backfill: BackfillConfig::default(),
+            auth: AuthConfig::default(),
+            limits: LimitsConfig::default(),
+            chains,
+            mock_event_min_delay_secs: 5,
+            mock_event_max_delay_secs: 15,
+        }
     }
 }
 
@@ -61,6 +395,10 @@ pub fn ensure_config_files_exist() -> std::io::Result<()> {
     let default_config_path = "config/default.toml";
     if std::fs::metadata(default_config_path).is_err() {
         let default_toml_content = r#"
+# Top-level keys must come before the first [table]
+mock_event_min_delay_secs = 5
+mock_event_max_delay_secs = 15
+
 [server]
 host = "127.0.0.1"
 port = 8080
@@ -68,8 +406,75 @@ port = 8080
 [logger]
 level = "info"
 
-mock_event_min_delay_secs = 5
-mock_event_max_delay_secs = 15
+# Export spans to an OpenTelemetry collector, W3C `traceparent` headers are honoured
+[telemetry]
+# Spans filtered out by the [logger] level are not exported either
+# otlp_endpoint = "http://localhost:4317"
+sampling_ratio = 1.0
+service_name = "chain-metadata-graphql"
+
+[storage]
+# "memory" keeps everything in-process and loses it on restart, "sqlite" persists to sqlite_path
+backend = "memory"
+sqlite_path = "data/chain_metadata.db"
+
+[ingestion]
+enabled = false
+reconnect_delay_secs = 5
+# Blocks a chain may fall behind its node before /readyz fails
+max_lag_blocks = 20
+
+# Historical backfills, see `backfill_from_block` on the chains
+[backfill]
+batch_size = 100
+parallelism = 4
+retry_delay_secs = 10
+
+# Queries over max_depth or max_complexity are refused before they run. Every client gets
+# rate_limit_burst operations, refilled at rate_limit_per_sec (0 turns rate limiting off)
+[limits]
+max_depth = 12
+max_complexity = 20000
+rate_limit_per_sec = 10.0
+rate_limit_burst = 50
+
+# Callers send `Authorization: Bearer <api key or JWT>`, subscriptions put it in their
+# connection_init payload. Scopes: "read" (queries), "subscribe", "admin" (mutations, implies all)
+[auth]
+required = false
+anonymous_scopes = ["read", "subscribe"]
+# JWT scopes come from the `scope` (space separated) or `scopes` claim, the caller from `sub`
+# jwt_hs256_secret = "change-me"
+# jwt_rs256_public_key_path = "config/jwt_public.pem"
+# jwt_issuer = "https://auth.example.com"
+# jwt_audience = "chain-metadata"
+
+# Keys until `rotateApiKeys` issues one with the same name
+# [[auth.api_keys]]
+# name = "admin"
+# key = "change-me"
+# scopes = ["admin"]
+
+# One entry per indexed chain, exposed through the `chains` query
+[[chains]]
+id = "polkadot-mainnet-mock"
+name = "Polkadot (Mock)"
+endpoint = "ws://127.0.0.1:9944"
+# Serve a recorded session from a local mock RPC server instead of a node
+# replay_file = "fixtures/rpc_recording.json"
+# Index history from this block up to the finalized head, requires ingestion
+# backfill_from_block = 1
+ss58_prefix = 0
+token_symbol = "MDOT"
+decimals = 10
+
+# [[chains]]
+# id = "kusama"
+# name = "Kusama"
+# endpoint = "wss://kusama-rpc.polkadot.io"
+# ss58_prefix = 2
+# token_symbol = "KSM"
+# decimals = 12
         "#;
         std::fs::write(default_config_path, default_toml_content)?;
         println!("Created default configuration file: {}", default_config_path);
@@ -1,10 +1,10 @@
 use async_graphql::dataloader::Loader;
-use async_graphql::{ID, FieldResult};
+use async_graphql::{Error as GraphQLError, ErrorExtensions, ID};
 use std::collections::HashMap;
-use std::sync::Arc;
-use crate::models::ChainInfo;
+use crate::models::{Block, ChainInfo, Event, Extrinsic};
 use crate::indexer::SubstrateIndexerService;
 use crate::errors::AppError;
+use crate::metrics::METRICS;
 use tracing::instrument;
 
 // Define a key for our Dataloader
@@ -22,14 +22,14 @@ impl ChainInfoLoader {
     }
 }
 
-#[async_trait::async_trait]
 impl Loader<ChainInfoLoaderKey> for ChainInfoLoader {
     type Value = ChainInfo;
-    type Error = Arc<AppError>; // Dataloader errors must be Arced
+    type Error = GraphQLError; // Cloned to every waiting key, keeps the error extensions
 
     #[instrument(name = "load_chain_infos", skip(self, keys))]
     async fn load(&self, keys: &[ChainInfoLoaderKey]) -> Result<HashMap<ChainInfoLoaderKey, Self::Value>, Self::Error> {
         tracing::debug!("Dataloader: loading ChainInfo for keys: {:?}", keys);
+        METRICS.observe_batch("chain_info", keys.len());
         let ids_to_fetch: Vec<ID> = keys.iter().map(|k| k.0.clone()).collect();
         
         // Use the batch fetch method from the service
@@ -37,7 +37,7 @@ impl Loader<ChainInfoLoaderKey> for ChainInfoLoader {
             .indexer_service
             .get_chain_infos_batch(&ids_to_fetch)
             .await
-            .map_err(|e| Arc::new(AppError::ServiceError(format!("Failed to batch fetch chain_infos: {}", e))))?;
+            .map_err(|e| AppError::ServiceError(format!("Failed to batch fetch chain_infos: {}", e.message)).extend())?;
 
         // Convert the result back to HashMap<ChainInfoLoaderKey, ChainInfo>
         let result = chain_infos_map
@@ -50,4 +50,167 @@ impl Loader<ChainInfoLoaderKey> for ChainInfoLoader {
 }
 
 // Convenience type for the Dataloader
-pub type AppDataloader = async_graphql::dataloader::DataLoader<ChainInfoLoader>; 
\ No newline at end of file
+pub type AppDataloader = async_graphql::dataloader::DataLoader<ChainInfoLoader>;
+
+// Blocks are keyed by chain and number
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct BlockLoaderKey(pub ID, pub u64);
+
+pub struct BlockLoader {
+    indexer_service: SubstrateIndexerService,
+}
+
+impl BlockLoader {
+    pub fn new(indexer_service: SubstrateIndexerService) -> Self {
+        Self { indexer_service }
+    }
+}
+
+impl Loader<BlockLoaderKey> for BlockLoader {
+    type Value = Block;
+    type Error = GraphQLError;
+
+    #[instrument(name = "load_blocks", skip(self, keys))]
+    async fn load(&self, keys: &[BlockLoaderKey]) -> Result<HashMap<BlockLoaderKey, Self::Value>, Self::Error> {
+        tracing::debug!("Dataloader: loading Blocks for keys: {:?}", keys);
+        METRICS.observe_batch("block", keys.len());
+        let mut result = HashMap::new();
+        for (chain_id, numbers) in group_by_chain(keys.iter().map(|k| (&k.0, k.1))) {
+            let blocks = self
+                .indexer_service
+                .get_blocks_batch(&chain_id, &numbers)
+                .await
+                .map_err(|e| AppError::ServiceError(format!("Failed to batch fetch blocks: {}", e.message)).extend())?;
+            result.extend(blocks.into_iter().map(|(number, block)| (BlockLoaderKey(chain_id.clone(), number), block)));
+        }
+        Ok(result)
+    }
+}
+
+// Events of a block, keyed by chain and block number
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct BlockEventsLoaderKey(pub ID, pub u64);
+
+pub struct BlockEventsLoader {
+    indexer_service: SubstrateIndexerService,
+}
+
+impl BlockEventsLoader {
+    pub fn new(indexer_service: SubstrateIndexerService) -> Self {
+        Self { indexer_service }
+    }
+}
+
+impl Loader<BlockEventsLoaderKey> for BlockEventsLoader {
+    type Value = Vec<Event>;
+    type Error = GraphQLError;
+
+    #[instrument(name = "load_block_events", skip(self, keys))]
+    async fn load(&self, keys: &[BlockEventsLoaderKey]) -> Result<HashMap<BlockEventsLoaderKey, Self::Value>, Self::Error> {
+        tracing::debug!("Dataloader: loading Events for blocks: {:?}", keys);
+        METRICS.observe_batch("block_events", keys.len());
+        let mut result: HashMap<BlockEventsLoaderKey, Vec<Event>> = HashMap::new();
+        for (chain_id, numbers) in group_by_chain(keys.iter().map(|k| (&k.0, k.1))) {
+            let events = self
+                .indexer_service
+                .list_events_in_blocks(&chain_id, &numbers)
+                .await
+                .map_err(|e| AppError::ServiceError(format!("Failed to batch fetch block events: {}", e.message)).extend())?;
+            // Already ordered by position, so each block's events stay in order
+            for event in events {
+                result
+                    .entry(BlockEventsLoaderKey(chain_id.clone(), event.block_number))
+                    .or_default()
+                    .push(event);
+            }
+        }
+        Ok(result)
+    }
+}
+
+// Extrinsics are keyed by chain and extrinsic id
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct ExtrinsicLoaderKey(pub ID, pub ID);
+
+pub struct ExtrinsicLoader {
+    indexer_service: SubstrateIndexerService,
+}
+
+impl ExtrinsicLoader {
+    pub fn new(indexer_service: SubstrateIndexerService) -> Self {
+        Self { indexer_service }
+    }
+}
+
+impl Loader<ExtrinsicLoaderKey> for ExtrinsicLoader {
+    type Value = Extrinsic;
+    type Error = GraphQLError;
+
+    #[instrument(name = "load_extrinsics", skip(self, keys))]
+    async fn load(&self, keys: &[ExtrinsicLoaderKey]) -> Result<HashMap<ExtrinsicLoaderKey, Self::Value>, Self::Error> {
+        tracing::debug!("Dataloader: loading Extrinsics for keys: {:?}", keys);
+        METRICS.observe_batch("extrinsic", keys.len());
+        let mut result = HashMap::new();
+        for (chain_id, ids) in group_by_chain(keys.iter().map(|k| (&k.0, k.1.clone()))) {
+            let extrinsics = self
+                .indexer_service
+                .get_extrinsics_batch(&chain_id, &ids)
+                .await
+                .map_err(|e| AppError::ServiceError(format!("Failed to batch fetch extrinsics: {}", e.message)).extend())?;
+            result.extend(extrinsics.into_iter().map(|(id, extrinsic)| (ExtrinsicLoaderKey(chain_id.clone(), id), extrinsic)));
+        }
+        Ok(result)
+    }
+}
+
+// Events emitted by an extrinsic, keyed by chain and extrinsic id
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct ExtrinsicEventsLoaderKey(pub ID, pub ID);
+
+pub struct ExtrinsicEventsLoader {
+    indexer_service: SubstrateIndexerService,
+}
+
+impl ExtrinsicEventsLoader {
+    pub fn new(indexer_service: SubstrateIndexerService) -> Self {
+        Self { indexer_service }
+    }
+}
+
+impl Loader<ExtrinsicEventsLoaderKey> for ExtrinsicEventsLoader {
+    type Value = Vec<Event>;
+    type Error = GraphQLError;
+
+    #[instrument(name = "load_extrinsic_events", skip(self, keys))]
+    async fn load(&self, keys: &[ExtrinsicEventsLoaderKey]) -> Result<HashMap<ExtrinsicEventsLoaderKey, Self::Value>, Self::Error> {
+        tracing::debug!("Dataloader: loading Events for extrinsics: {:?}", keys);
+        METRICS.observe_batch("extrinsic_events", keys.len());
+        let mut result: HashMap<ExtrinsicEventsLoaderKey, Vec<Event>> = HashMap::new();
+        for (chain_id, ids) in group_by_chain(keys.iter().map(|k| (&k.0, k.1.clone()))) {
+            let events = self
+                .indexer_service
+                .list_events_for_extrinsics(&chain_id, &ids)
+                .await
+                .map_err(|e| AppError::ServiceError(format!("Failed to batch fetch extrinsic events: {}", e.message)).extend())?;
+            for event in events {
+                let Some(extrinsic_id) = event.extrinsic_id.clone() else {
+                    continue;
+                };
+                result
+                    .entry(ExtrinsicEventsLoaderKey(chain_id.clone(), ID::from(extrinsic_id)))
+                    .or_default()
+                    .push(event);
+            }
+        }
+        Ok(result)
+    }
+}
+
+// A batch can span chains, the store is queried once per chain.
+fn group_by_chain<'a, K>(keys: impl Iterator<Item = (&'a ID, K)>) -> HashMap<ID, Vec<K>> {
+    let mut grouped: HashMap<ID, Vec<K>> = HashMap::new();
+    for (chain_id, number) in keys {
+        grouped.entry(chain_id.clone()).or_default().push(number);
+    }
+    grouped
+}
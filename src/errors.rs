@@ -1,5 +1,5 @@
 use thiserror::Error;
-use async_graphql::{Error as GraphQLError, ErrorExtensions};
+use async_graphql::{Error as GraphQLError, ErrorExtensions, ServerError};
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -20,21 +20,94 @@ pub enum AppError {
 
     #[error("Service error: {0}")]
     ServiceError(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("RPC error: {0}")]
+    Rpc(String),
+
+    #[error("Decode error: {0}")]
+    Decode(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Rate limit exceeded, retry in {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("Subscriber lagged behind by {0} events, they are being replayed from the store")]
+    Lagged(u64),
+
+    #[error("Chain reorganized from block {from_block}, events of the orphaned blocks are retracted and their replacements follow")]
+    Reorg { from_block: u64, orphaned_block_hashes: Vec<String> },
 }
 
 impl ErrorExtensions for AppError {
     fn extend(&self) -> GraphQLError {
-        GraphQLError::new(format!("{}", self)).extend_with(|_err, e| match self {
-            AppError::NotFound(reason) => e.set("code", "NOT_FOUND").set("reason", reason.clone()),
-            AppError::Config(s) => e.set("code", "CONFIG_ERROR").set("details", s.to_string()),
-            AppError::Internal(s) => e.set("code", "INTERNAL_SERVER_ERROR").set("details", s.clone()),
-            AppError::GraphQLExecution(s) => e.set("code", "GRAPHQL_EXECUTION_ERROR").set("details", s.clone()),
-            AppError::ServiceError(s) => e.set("code", "SERVICE_ERROR").set("details", s.clone()),
-            AppError::Io(s) => e.set("code", "IO_ERROR").set("details", s.to_string()),
+        GraphQLError::new(format!("{}", self)).extend_with(|_err, e| {
+            e.set("code", self.code());
+            match self {
+                AppError::NotFound(reason) => e.set("reason", reason.clone()),
+                AppError::Config(s) => e.set("details", s.to_string()),
+                AppError::Io(s) => e.set("details", s.to_string()),
+                AppError::Database(s) => e.set("details", s.to_string()),
+                AppError::Internal(s)
+                | AppError::GraphQLExecution(s)
+                | AppError::ServiceError(s)
+                | AppError::Rpc(s)
+                | AppError::Decode(s)
+                | AppError::InvalidInput(s)
+                | AppError::Unauthorized(s)
+                | AppError::Forbidden(s) => e.set("details", s.clone()),
+                AppError::RateLimited { retry_after_secs } => e.set("retryAfterSecs", *retry_after_secs),
+                AppError::Lagged(skipped) => e.set("skipped", *skipped),
+                AppError::Reorg { from_block, orphaned_block_hashes } => {
+                    e.set("fromBlock", *from_block);
+                    e.set("orphanedBlockHashes", orphaned_block_hashes.clone());
+                }
+            }
         })
     }
 }
 
+impl AppError {
+    // The `code` extension clients branch on
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Config(_) => "CONFIG_ERROR",
+            AppError::Internal(_) => "INTERNAL_SERVER_ERROR",
+            AppError::GraphQLExecution(_) => "GRAPHQL_EXECUTION_ERROR",
+            AppError::ServiceError(_) => "SERVICE_ERROR",
+            AppError::Io(_) => "IO_ERROR",
+            AppError::Database(_) => "DATABASE_ERROR",
+            AppError::Rpc(_) => "RPC_ERROR",
+            AppError::Decode(_) => "DECODE_ERROR",
+            AppError::InvalidInput(_) => "BAD_USER_INPUT",
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::RateLimited { .. } => "RATE_LIMITED",
+            AppError::Lagged(_) => "SUBSCRIPTION_LAGGED",
+            AppError::Reorg { .. } => "CHAIN_REORG",
+        }
+    }
+
+    // For errors raised before any field is resolved, they have no location
+    pub fn to_server_error(&self) -> ServerError {
+        let error = self.extend();
+        let mut server_error = ServerError::new(error.message, None);
+        server_error.extensions = error.extensions;
+        server_error
+    }
+}
+
 // Allow converting AppError to FieldResult (which is Result<T, GraphQLError>)
 pub type Result<T, E = AppError> = std::result::Result<T, E>;
 
@@ -42,4 +115,7 @@ impl From<AppError> for GraphQLError {
     fn from(err: AppError) -> Self {
         err.extend()
     }
-} 
\ No newline at end of file
+}
+
+// Plain HTTP handlers answer with a 500 and the message
+impl actix_web::ResponseError for AppError {}
@@ -0,0 +1,103 @@
+use crate::config::IngestionConfig;
+use crate::indexer::SubstrateIndexerService;
+use crate::models::{ChainHealth, HealthState, HealthStatus, StoreHealth, WorkerKind, WorkerState};
+use crate::workers::Workers;
+use async_graphql::ID;
+use chrono::Utc;
+use std::time::{Duration, Instant};
+
+// A store slower than this to answer counts as unreachable
+const STORE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Checks behind `healthCheck`, /healthz and /readyz. Chains an admin paused or stopped
+// don't count against readiness, their lag is on purpose.
+#[derive(Clone)]
+pub struct HealthChecker {
+    service: SubstrateIndexerService,
+    workers: Workers,
+    max_lag_blocks: u64,
+}
+
+impl HealthChecker {
+    pub fn new(service: SubstrateIndexerService, workers: Workers, config: &IngestionConfig) -> Self {
+        Self {
+            service,
+            workers,
+            max_lag_blocks: config.max_lag_blocks,
+        }
+    }
+
+    pub async fn check(&self) -> HealthStatus {
+        let store = self.check_store().await;
+        let mut chains = Vec::new();
+        for chain_id in self.service.chain_ids() {
+            chains.push(self.check_chain(chain_id, store.reachable).await);
+        }
+        let broadcast = self.service.broadcast_health();
+
+        let live = chains.iter().all(|c| c.worker_alive);
+        let ready = live && store.reachable && chains.iter().all(|c| c.ready);
+        let status = if !ready {
+            HealthState::Fail
+        } else if broadcast.queued >= broadcast.capacity {
+            HealthState::Warn
+        } else {
+            HealthState::Pass
+        };
+        HealthStatus {
+            status,
+            live,
+            ready,
+            store,
+            broadcast,
+            chains,
+            checked_at: Utc::now(),
+        }
+    }
+
+    async fn check_store(&self) -> StoreHealth {
+        let started = Instant::now();
+        let error = match tokio::time::timeout(STORE_TIMEOUT, self.service.store().ping()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some(format!("No answer within {}s", STORE_TIMEOUT.as_secs())),
+        };
+        StoreHealth {
+            reachable: error.is_none(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            error,
+        }
+    }
+
+    async fn check_chain(&self, chain_id: ID, store_reachable: bool) -> ChainHealth {
+        let worker = self.workers.status(&chain_id).await;
+        let worker_alive = self.workers.is_alive(&chain_id).await.unwrap_or(true);
+        let (indexed_height, finalized_height) = if store_reachable {
+            let store = self.service.store();
+            (
+                store.indexed_block_height(&chain_id).await.ok().flatten(),
+                store.finalized_block_height(&chain_id).await.ok().flatten(),
+            )
+        } else {
+            (None, None)
+        };
+        let ingesting = worker
+            .as_ref()
+            .is_some_and(|w| w.kind == WorkerKind::Ingestion && w.state == WorkerState::Running);
+        let node_head = self.service.node_head(&chain_id);
+        let lag_blocks = match node_head {
+            Some(head) if ingesting => Some(head.saturating_sub(indexed_height.unwrap_or(0))),
+            _ => None,
+        };
+        ChainHealth {
+            ready: worker_alive && lag_blocks.is_none_or(|lag| lag <= self.max_lag_blocks),
+            chain_id,
+            worker,
+            worker_alive,
+            node_head,
+            indexed_height,
+            finalized_height,
+            lag_blocks,
+        }
+    }
+}
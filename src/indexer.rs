@@ -1,188 +1,879 @@
-use crate::models::{ChainInfo, Event, EventFilterInput};
-use crate::errors::AppError;
-use crate::config::AppConfig;
+use crate::models::{
+    parse_data_path, Account, BackfillStatus, Block, BlockConnection, BroadcastHealth, ChainInfo, DataFieldStats, Event, EventConnection,
+    EventConnectionFields, EventCursor, EventFilterInput, EventNameCount, EventStats, Extrinsic, ExtrinsicConnection, ExtrinsicCursor,
+    ExtrinsicFilterInput, RuntimeMetadata, TimeBucket, TimeBucketCount,
+};
+use crate::address::{AccountFields, Address, ALICE};
+use crate::errors::{AppError, Result};
+use crate::config::{AppConfig, ChainConfig};
+use crate::metadata::DecodedMetadata;
+use crate::metrics::METRICS;
+use crate::store::{BlockPage, EventPage, ExtrinsicPage, Page, RawMetadata, Store};
+use async_graphql::connection::{Edge, OpaqueCursor};
 use async_graphql::{ID, FieldResult};
-use chrono::{Utc, Duration as ChronoDuration};
-use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use tokio::sync::broadcast::{self, Sender as BroadcastSender};
-use tokio_stream::wrappers::BroadcastStream;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use tokio::sync::broadcast::{self, error::RecvError, Sender as BroadcastSender};
 use tokio_stream::Stream;
 use uuid::Uuid;
 use rand::Rng;
 use serde_json::json;
-use tracing::{info, error, instrument};
-
-// Mock data store
-// ChainInfo remains fairly static, so Lazy is fine.
-pub static MOCK_CHAIN_INFO: Lazy<ChainInfo> = Lazy::new(|| ChainInfo {
-    id: ID::from("polkadot-mainnet-mock"),
-    name: "Polkadot (Mock)".to_string(),
-    version: "0.9.99-mock".to_string(),
-    token_symbol: "MDOT".to_string(),
-    decimals: 10,
-    ssv58_prefix: 0,
-    last_updated: Utc::now(),
-});
-
-struct MockEventStore {
-    events: HashMap<ID, Event>,
-    event_sender: BroadcastSender<Event>,
-    chain_id: ID,
+use tracing::{debug, info, error, instrument, warn};
+
+// Events read from the store per round trip while replaying history to a subscriber
+const REPLAY_PAGE_SIZE: usize = 500;
+// Notifications buffered for the slowest subscriber before it lags
+const NOTIFICATION_CAPACITY: usize = 100;
+
+// The simulator finalizes blocks this far behind its head
+const MOCK_FINALITY_LAG: u64 = 2;
+// Chance that a simulated block replaces unfinalized blocks instead of extending the head
+const MOCK_REORG_PROBABILITY: f64 = 0.1;
+
+// Where an event subscription starts.
+#[derive(Debug, Clone, Copy)]
+pub enum ReplayFrom {
+    // Only events published after subscribing
+    Live,
+    // All stored events, oldest first, then live ones
+    Beginning,
+    // Stored events after this position, then live ones
+    After(EventCursor),
 }
 
-// Use an Arc<Mutex<...>> for the event store to allow it to be shared safely across async tasks
-// and potentially with Dataloaders later.
-pub static MOCK_EVENT_STORE: Lazy<Arc<Mutex<MockEventStore>>> = Lazy::new(|| {
-    let (tx, _) = broadcast::channel(100);
-    let chain_id = MOCK_CHAIN_INFO.id.clone();
-    let mut events = HashMap::new();
-    for i in 0..5 {
-        let event_id = ID::from(Uuid::new_v4().to_string());
-        let event = Event {
-            id: event_id.clone(),
-            block_number: 10000 + i,
-            extrinsic_id: Some(format!("0x{}", Uuid::new_v4().to_simple())),
-            timestamp: Utc::now() - ChronoDuration::seconds((5 - i) as i64 * 10),
-            pallet_name: "Balances".to_string(),
-            event_name: "Transfer".to_string(),
-            data: json!({ "from": "Alice", "to": "Bob", "amount": (100 + i) * 1_000_000_000_000u128 }),
-            chain_id: chain_id.clone(),
-        };
-        events.insert(event_id, event);
-    }
-    Arc::new(Mutex::new(MockEventStore {
-        events,
-        event_sender: tx,
-        chain_id,
-    }))
-});
+// What the service fans out to subscribers.
+#[derive(Debug, Clone)]
+enum ChainNotification {
+    // A newly stored event, or an unfinalized one that was just finalized
+    Event(Event),
+    // Unfinalized blocks from `from_block` on were orphaned and their data deleted
+    Reorg {
+        chain_id: ID,
+        from_block: u64,
+        orphaned_block_hashes: Vec<String>,
+    },
+}
 
 #[derive(Clone)]
 pub struct SubstrateIndexerService {
     config: Arc<AppConfig>, // Share config via Arc
-    // In a real app, this might hold a DB connection pool or an HTTP client for the indexer
-    event_store: Arc<Mutex<MockEventStore>>,
+    // Storage backend selected via `AppConfig.storage`
+    store: Arc<dyn Store>,
+    notification_sender: BroadcastSender<ChainNotification>,
+    // The configured chains followed by the ones registered at runtime
+    chains: Arc<RwLock<Vec<ChainConfig>>>,
+    // Decoding metadata is expensive, keep the GraphQL view of the latest one per chain
+    metadata_cache: Arc<Mutex<HashMap<ID, Arc<RuntimeMetadata>>>>,
+    // Derived from the same metadata, where each event and call keeps its account ids
+    account_fields: Arc<Mutex<HashMap<ID, Arc<AccountFields>>>>,
+    // Best block each chain's node last reported to ingestion
+    node_heads: Arc<Mutex<HashMap<ID, u64>>>,
 }
 
 impl SubstrateIndexerService {
-    #[instrument(skip(config))]
-    pub fn new(config: AppConfig) -> Self {
+    #[instrument(skip(config, store))]
+    pub fn new(config: AppConfig, store: Arc<dyn Store>) -> Self {
         info!("Initializing SubstrateIndexerService");
+        let (notification_sender, _) = broadcast::channel(NOTIFICATION_CAPACITY);
         Self {
+            chains: Arc::new(RwLock::new(config.chains.clone())),
             config: Arc::new(config),
-            event_store: MOCK_EVENT_STORE.clone(),
+            store,
+            notification_sender,
+            metadata_cache: Arc::new(Mutex::new(HashMap::new())),
+            account_fields: Arc::default(),
+            node_heads: Arc::default(),
+        }
+    }
+
+    pub fn store(&self) -> Arc<dyn Store> {
+        self.store.clone()
+    }
+
+    // The chain registry: configured chains in configuration order, then the ones
+    // registered at runtime in registration order.
+    pub fn chains(&self) -> Vec<ChainConfig> {
+        self.chains.read().map(|chains| chains.clone()).unwrap_or_default()
+    }
+
+    pub fn chain_ids(&self) -> Vec<ID> {
+        self.chains().iter().map(|c| ID::from(c.id.as_str())).collect()
+    }
+
+    pub fn chain(&self, chain_id: &ID) -> Result<ChainConfig> {
+        self.chains()
+            .into_iter()
+            .find(|c| c.id == chain_id.as_str())
+            .ok_or_else(|| AppError::NotFound(format!("Chain is not registered: {}", chain_id.as_str())))
+    }
+
+    fn ensure_registered(&self, chain_id: &ID) -> Result<()> {
+        self.chain(chain_id).map(|_| ())
+    }
+
+    fn write_chains(&self) -> Result<std::sync::RwLockWriteGuard<'_, Vec<ChainConfig>>> {
+        self.chains
+            .write()
+            .map_err(|e| AppError::Internal(format!("Failed to lock chain registry: {}", e)))
+    }
+
+    // Add the chains registered at runtime by earlier runs to the registry and make sure
+    // every registered chain has a ChainInfo row.
+    #[instrument(skip(self))]
+    pub async fn register_chains(&self) -> Result<()> {
+        for chain in self.store.list_registered_chains().await? {
+            let mut chains = self.write_chains()?;
+            if chains.iter().any(|c| c.id == chain.id) {
+                warn!(chain_id = %chain.id, "Registered chain is also configured, using the configuration");
+            } else {
+                chains.push(chain);
+            }
+        }
+        for chain in self.chains() {
+            self.sync_registered_chain_info(&chain).await?;
+            info!(chain_id = %chain.id, name = %chain.name, "Registered chain");
+        }
+        Ok(())
+    }
+
+    // Add `chain` to the registry and persist it so it survives restarts. The id is taken
+    // in the registry first, so concurrent registrations of it cannot both be persisted.
+    #[instrument(skip(self, chain), fields(chain_id = %chain.id))]
+    pub async fn register_chain(&self, chain: ChainConfig) -> Result<ChainInfo> {
+        {
+            let mut chains = self.write_chains()?;
+            if chains.iter().any(|c| c.id == chain.id) {
+                return Err(AppError::InvalidInput(format!("Chain is already registered: {}", chain.id)));
+            }
+            chains.push(chain.clone());
+        }
+        if let Err(e) = self.store.save_registered_chain(chain.clone()).await {
+            self.write_chains()?.retain(|c| c.id != chain.id);
+            return Err(e);
+        }
+        self.sync_registered_chain_info(&chain).await?;
+        info!(name = %chain.name, "Registered chain");
+        self.store
+            .get_chain_info(&ID::from(chain.id.as_str()))
+            .await?
+            .ok_or_else(|| AppError::Internal(format!("ChainInfo missing after registering {}", chain.id)))
+    }
+
+    // Drop a chain registered at runtime from the registry. Its indexed data is kept.
+    // Configured chains can only be removed from the configuration.
+    #[instrument(skip(self))]
+    pub async fn unregister_chain(&self, chain_id: &ID) -> Result<()> {
+        self.ensure_registered(chain_id)?;
+        if self.config.chains.iter().any(|c| c.id == chain_id.as_str()) {
+            return Err(AppError::InvalidInput(format!(
+                "Chain {} is configured, remove it from the configuration instead",
+                chain_id.as_str()
+            )));
+        }
+        self.store.delete_registered_chain(chain_id).await?;
+        self.write_chains()?.retain(|c| c.id != chain_id.as_str());
+        info!("Unregistered chain");
+        Ok(())
+    }
+
+    // Static ChainInfo fields come from the registry, the runtime version is kept from
+    // the stored row (ingestion refreshes it).
+    async fn sync_registered_chain_info(&self, chain: &ChainConfig) -> Result<()> {
+        let id = ID::from(chain.id.as_str());
+        let version = match self.store.get_chain_info(&id).await? {
+            Some(current) => current.version,
+            None => "unknown".to_string(),
+        };
+        self.store
+            .upsert_chain_info(ChainInfo {
+                id,
+                name: chain.name.clone(),
+                version,
+                token_symbol: chain.token_symbol.clone(),
+                decimals: chain.decimals,
+                ssv58_prefix: chain.ss58_prefix,
+                last_updated: Utc::now(),
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_chain_info(&self, chain_id: &ID) -> FieldResult<ChainInfo> {
+        self.ensure_registered(chain_id)?;
+        match self.store.get_chain_info(chain_id).await? {
+            Some(chain_info) => Ok(chain_info),
+            None => Err(AppError::NotFound(format!("ChainInfo not found for ID: {}", chain_id.as_str())).into()),
         }
     }
 
     #[instrument(skip(self))]
-    pub async fn get_chain_info(&self) -> FieldResult<ChainInfo> {
-        // In a real scenario, this might involve an async call.
-        // For Dataloader, we might need a version that takes multiple IDs.
-        Ok(MOCK_CHAIN_INFO.clone())
+    pub async fn list_chains(&self) -> FieldResult<Vec<ChainInfo>> {
+        let ids = self.chain_ids();
+        let mut chain_infos = self.store.get_chain_infos_batch(&ids).await?;
+        Ok(ids.iter().filter_map(|id| chain_infos.remove(id)).collect())
     }
-    
+
     // Example for Dataloader: batch fetch chain_infos
     #[instrument(skip(self, ids))]
     pub async fn get_chain_infos_batch(&self, ids: &[ID]) -> FieldResult<HashMap<ID, ChainInfo>> {
-        let mut result = HashMap::new();
-        for id in ids {
-            // Simulate fetching; in our mock, only one chain info exists
-            if *id == MOCK_CHAIN_INFO.id {
-                result.insert(id.clone(), MOCK_CHAIN_INFO.clone());
-            }
+        Ok(self.store.get_chain_infos_batch(ids).await?)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn current_block_height(&self, chain_id: &ID) -> FieldResult<u64> {
+        Ok(self.store.max_block_number(chain_id).await?.unwrap_or(0))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn finalized_block_height(&self, chain_id: &ID) -> FieldResult<u64> {
+        Ok(self.store.finalized_block_height(chain_id).await?.unwrap_or(0))
+    }
+
+    // Progress of the chain's latest backfill, `None` if it was never backfilled.
+    #[instrument(skip(self))]
+    pub async fn backfill_status(&self, chain_id: &ID) -> FieldResult<Option<BackfillStatus>> {
+        self.ensure_registered(chain_id)?;
+        Ok(self.store.get_backfill(chain_id).await?)
+    }
+
+    // Latest runtime metadata seen for the chain, `None` until ingestion has fetched it.
+    #[instrument(skip(self))]
+    pub async fn runtime_metadata(&self, chain_id: &ID) -> FieldResult<Option<Arc<RuntimeMetadata>>> {
+        self.ensure_registered(chain_id)?;
+        if let Some(cached) = self.metadata_cache()?.get(chain_id).cloned() {
+            return Ok(Some(cached));
+        }
+        let Some(raw) = self.store.get_runtime_metadata(chain_id).await? else {
+            return Ok(None);
+        };
+        let metadata = Arc::new(DecodedMetadata::decode(&raw.bytes, raw.spec_version)?.to_graphql());
+        self.metadata_cache()?.insert(chain_id.clone(), metadata.clone());
+        Ok(Some(metadata))
+    }
+
+    // Persist raw metadata fetched by ingestion and drop the stale decoded copy.
+    #[instrument(skip(self, bytes))]
+    pub async fn save_runtime_metadata(&self, chain_id: &ID, spec_version: u32, bytes: Vec<u8>) -> Result<()> {
+        self.store
+            .save_runtime_metadata(chain_id, RawMetadata { spec_version, bytes })
+            .await?;
+        self.metadata_cache()?.remove(chain_id);
+        self.account_fields_cache()?.remove(chain_id);
+        Ok(())
+    }
+
+    // Where the chain's events and calls hold account ids, see `AccountFields`.
+    pub async fn account_fields(&self, chain_id: &ID) -> FieldResult<Arc<AccountFields>> {
+        if let Some(cached) = self.account_fields_cache()?.get(chain_id).cloned() {
+            return Ok(cached);
         }
-        Ok(result)
+        let fields = match self.runtime_metadata(chain_id).await? {
+            Some(metadata) => AccountFields::from_metadata(&metadata),
+            None => AccountFields::well_known(),
+        };
+        let fields = Arc::new(fields);
+        self.account_fields_cache()?.insert(chain_id.clone(), fields.clone());
+        Ok(fields)
+    }
+
+    fn account_fields_cache(&self) -> Result<MutexGuard<'_, HashMap<ID, Arc<AccountFields>>>> {
+        self.account_fields
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock account fields cache: {}", e)))
+    }
+
+    pub fn record_node_head(&self, chain_id: &ID, number: u64) {
+        METRICS.chain_head.with_label_values(&[chain_id.as_str()]).set(number as i64);
+        if let Ok(mut heads) = self.node_heads.lock() {
+            heads.insert(chain_id.clone(), number);
+        }
+    }
+
+    pub fn node_head(&self, chain_id: &ID) -> Option<u64> {
+        self.node_heads.lock().ok()?.get(chain_id).copied()
+    }
+
+    pub fn broadcast_health(&self) -> BroadcastHealth {
+        BroadcastHealth {
+            subscribers: self.notification_sender.receiver_count(),
+            queued: self.notification_sender.len(),
+            capacity: NOTIFICATION_CAPACITY,
+        }
+    }
+
+    fn metadata_cache(&self) -> Result<MutexGuard<'_, HashMap<ID, Arc<RuntimeMetadata>>>> {
+        self.metadata_cache
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock metadata cache: {}", e)))
     }
 
     #[instrument(skip(self))]
-    pub async fn get_event_by_id(&self, id: ID) -> FieldResult<Option<Event>> {
-        let store = self.event_store.lock().map_err(|e| AppError::Internal(format!("Failed to lock event store: {}", e)))?;
-        Ok(store.events.get(&id).cloned())
+    pub async fn get_event_by_id(&self, chain_id: &ID, id: ID) -> FieldResult<Option<Event>> {
+        self.ensure_registered(chain_id)?;
+        let event = self.store.get_event_by_id(&id).await?;
+        Ok(event.filter(|e| &e.chain_id == chain_id))
     }
 
+    // One Relay page of the chain's events. An extra row is fetched to tell whether
+    // more events exist past the requested end of the page.
     #[instrument(skip(self, filter))]
-    pub async fn list_events(&self, filter: Option<EventFilterInput>) -> FieldResult<Vec<Event>> {
-        let store = self.event_store.lock().map_err(|e| AppError::Internal(format!("Failed to lock event store: {}", e)))?;
-        let mut events: Vec<Event> = store.events.values().cloned().collect();
-
-        if let Some(f) = filter {
-            events.retain(|e| {
-                let mut matches = true;
-                if let Some(pallet_name) = &f.pallet_name_eq {
-                    matches &= &e.pallet_name == pallet_name;
-                }
-                if let Some(event_name) = &f.event_name_eq {
-                    matches &= &e.event_name == event_name;
-                }
-                if let Some(bn_gte) = f.block_number_gte {
-                    matches &= e.block_number >= bn_gte;
-                }
-                if let Some(bn_lte) = f.block_number_lte {
-                    matches &= e.block_number <= bn_lte;
-                }
-                matches
-            });
+    pub async fn list_events(
+        &self,
+        chain_id: &ID,
+        filter: Option<EventFilterInput>,
+        page: EventPage,
+        with_total_count: bool,
+    ) -> FieldResult<EventConnection> {
+        self.ensure_registered(chain_id)?;
+        if let Some(filter) = &filter {
+            filter.validate()?;
         }
-        events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp)); // Sort by newest first
-        Ok(events)
+        let probe = EventPage { limit: page.limit + 1, ..page.clone() };
+        let mut events = self.store.list_events(chain_id, filter.as_ref(), &probe).await?;
+        let (has_previous_page, has_next_page) = trim_page(&mut events, &page);
+
+        let total_count = if with_total_count {
+            Some(self.store.count_events(chain_id, filter.as_ref()).await?)
+        } else {
+            None
+        };
+        let mut connection = EventConnection::with_additional_fields(
+            has_previous_page,
+            has_next_page,
+            EventConnectionFields { total_count },
+        );
+        connection.edges.extend(
+            events
+                .into_iter()
+                .map(|event| Edge::new(OpaqueCursor(EventCursor::from(&event)), event)),
+        );
+        Ok(connection)
+    }
+
+    // Aggregates over the chain's matching events. Nothing is computed until one of them is
+    // resolved, see the `event_stats_*` methods.
+    #[instrument(skip(self, filter))]
+    pub fn event_stats(&self, chain_id: &ID, filter: Option<EventFilterInput>) -> FieldResult<EventStats> {
+        self.ensure_registered(chain_id)?;
+        if let Some(filter) = &filter {
+            filter.validate()?;
+        }
+        Ok(EventStats {
+            chain_id: chain_id.clone(),
+            filter,
+        })
+    }
+
+    #[instrument(skip(self, filter))]
+    pub async fn event_stats_count(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> FieldResult<u64> {
+        Ok(self.store.count_events(chain_id, filter).await?)
+    }
+
+    #[instrument(skip(self, filter))]
+    pub async fn event_stats_by_name(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> FieldResult<Vec<EventNameCount>> {
+        Ok(self.store.count_events_by_name(chain_id, filter).await?)
+    }
+
+    #[instrument(skip(self, filter))]
+    pub async fn event_stats_histogram(
+        &self,
+        chain_id: &ID,
+        filter: Option<&EventFilterInput>,
+        bucket: TimeBucket,
+    ) -> FieldResult<Vec<TimeBucketCount>> {
+        Ok(self.store.count_events_by_time(chain_id, filter, bucket.millis()).await?)
+    }
+
+    #[instrument(skip(self, filter))]
+    pub async fn event_stats_data(&self, chain_id: &ID, filter: Option<&EventFilterInput>, path: &str) -> FieldResult<DataFieldStats> {
+        let segments = parse_data_path(path).ok_or_else(|| AppError::InvalidInput(format!("Invalid data path: {:?}", path)))?;
+        Ok(self.store.event_data_stats(chain_id, filter, &segments).await?)
     }
 
     #[instrument(skip(self))]
-    pub async fn watch_events(&self) -> impl Stream<Item = Event> {
-        let rx = self.event_store.lock().unwrap().event_sender.subscribe(); // unwrap is fine here for a global static
-        BroadcastStream::new(rx).filter_map(|result| async move {
-            match result {
-                Ok(event) => Some(event),
-                Err(e) => {
-                    error!("Broadcast receive error: {}", e);
-                    None
-                }
+    pub async fn get_block_by_number(&self, chain_id: &ID, number: u64) -> FieldResult<Option<Block>> {
+        self.ensure_registered(chain_id)?;
+        Ok(self.store.get_block_by_number(chain_id, number).await?)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_block_by_hash(&self, chain_id: &ID, hash: &str) -> FieldResult<Option<Block>> {
+        self.ensure_registered(chain_id)?;
+        Ok(self.store.get_block_by_hash(chain_id, &hash.to_ascii_lowercase()).await?)
+    }
+
+    // One Relay page of the chain's blocks, see `list_events`.
+    #[instrument(skip(self))]
+    pub async fn list_blocks(&self, chain_id: &ID, finalized_only: bool, page: BlockPage) -> FieldResult<BlockConnection> {
+        self.ensure_registered(chain_id)?;
+        let probe = BlockPage { limit: page.limit + 1, ..page.clone() };
+        let mut blocks = self.store.list_blocks(chain_id, finalized_only, &probe).await?;
+        let (has_previous_page, has_next_page) = trim_page(&mut blocks, &page);
+
+        let mut connection = BlockConnection::new(has_previous_page, has_next_page);
+        connection.edges.extend(
+            blocks
+                .into_iter()
+                .map(|block| Edge::new(OpaqueCursor(block.number), block)),
+        );
+        Ok(connection)
+    }
+
+    // Batch fetches for the block dataloaders
+    #[instrument(skip(self, numbers))]
+    pub async fn get_blocks_batch(&self, chain_id: &ID, numbers: &[u64]) -> FieldResult<HashMap<u64, Block>> {
+        Ok(self.store.get_blocks_batch(chain_id, numbers).await?)
+    }
+
+    #[instrument(skip(self, block_numbers))]
+    pub async fn list_events_in_blocks(&self, chain_id: &ID, block_numbers: &[u64]) -> FieldResult<Vec<Event>> {
+        Ok(self.store.list_events_in_blocks(chain_id, block_numbers).await?)
+    }
+
+    #[instrument(skip(self, block), fields(chain_id = %block.chain_id.as_str(), number = block.number))]
+    pub async fn save_block(&self, block: Block) -> Result<()> {
+        self.store.upsert_block(block).await
+    }
+
+    // Mark the chain finalized up to `number`. Events of the newly finalized blocks are
+    // broadcast again, now finalized, for subscribers that only want those.
+    #[instrument(skip(self))]
+    pub async fn finalize(&self, chain_id: &ID, number: u64) -> Result<()> {
+        let finalized = self.store.finalize_blocks(chain_id, number).await?;
+        if finalized.is_empty() {
+            return Ok(());
+        }
+        debug!(blocks = finalized.len(), "Finalized blocks");
+        for event in self.store.list_events_in_blocks(chain_id, &finalized).await? {
+            // No subscribers is fine, see `publish_event`
+            let _ = self.notification_sender.send(ChainNotification::Event(event));
+        }
+        Ok(())
+    }
+
+    // Drop the blocks from `from_block` on, which another branch replaced, with everything
+    // indexed from them, and notify subscribers. Finalized blocks are never rolled back.
+    #[instrument(skip(self))]
+    pub async fn rollback(&self, chain_id: &ID, from_block: u64) -> Result<()> {
+        if let Some(finalized) = self.store.finalized_block_height(chain_id).await? {
+            if from_block <= finalized {
+                return Err(AppError::ServiceError(format!(
+                    "Refusing to roll back to block {}, block {} is finalized",
+                    from_block, finalized
+                )));
             }
+        }
+        let orphaned = self.store.rollback_blocks(chain_id, from_block).await?;
+        if orphaned.is_empty() {
+            return Ok(());
+        }
+        let orphaned_block_hashes: Vec<String> = orphaned.into_iter().map(|b| b.hash).collect();
+        warn!(from_block, orphaned = orphaned_block_hashes.len(), "Chain reorganized, rolled back unfinalized blocks");
+        let _ = self.notification_sender.send(ChainNotification::Reorg {
+            chain_id: chain_id.clone(),
+            from_block,
+            orphaned_block_hashes,
+        });
+        Ok(())
+    }
+
+    // Delete the chain's events older than `before`, returns how many were deleted.
+    #[instrument(skip(self))]
+    pub async fn purge_events(&self, chain_id: &ID, before: DateTime<Utc>) -> Result<u64> {
+        self.ensure_registered(chain_id)?;
+        let deleted = self.store.delete_events_before(chain_id, before).await?;
+        info!(deleted, "Purged events");
+        Ok(deleted)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_extrinsic(&self, chain_id: &ID, id: &ID) -> FieldResult<Option<Extrinsic>> {
+        self.ensure_registered(chain_id)?;
+        Ok(self.store.get_extrinsic(chain_id, id).await?)
+    }
+
+    // One Relay page of the chain's extrinsics, see `list_events`.
+    #[instrument(skip(self, filter))]
+    pub async fn list_extrinsics(
+        &self,
+        chain_id: &ID,
+        filter: Option<ExtrinsicFilterInput>,
+        page: ExtrinsicPage,
+    ) -> FieldResult<ExtrinsicConnection> {
+        self.ensure_registered(chain_id)?;
+        let probe = ExtrinsicPage { limit: page.limit + 1, ..page.clone() };
+        let mut extrinsics = self.store.list_extrinsics(chain_id, filter.as_ref(), &probe).await?;
+        let (has_previous_page, has_next_page) = trim_page(&mut extrinsics, &page);
+
+        let mut connection = ExtrinsicConnection::new(has_previous_page, has_next_page);
+        connection.edges.extend(
+            extrinsics
+                .into_iter()
+                .map(|extrinsic| Edge::new(OpaqueCursor(ExtrinsicCursor::from(&extrinsic)), extrinsic)),
+        );
+        Ok(connection)
+    }
+
+    // Batch fetches for the extrinsic dataloaders
+    #[instrument(skip(self, ids))]
+    pub async fn get_extrinsics_batch(&self, chain_id: &ID, ids: &[ID]) -> FieldResult<HashMap<ID, Extrinsic>> {
+        Ok(self.store.get_extrinsics_batch(chain_id, ids).await?)
+    }
+
+    #[instrument(skip(self, extrinsic_ids))]
+    pub async fn list_events_for_extrinsics(&self, chain_id: &ID, extrinsic_ids: &[ID]) -> FieldResult<Vec<Event>> {
+        Ok(self.store.list_events_for_extrinsics(chain_id, extrinsic_ids).await?)
+    }
+
+    #[instrument(skip(self))]
+    pub fn account(&self, chain_id: &ID, address: Address) -> FieldResult<Account> {
+        self.ensure_registered(chain_id)?;
+        Ok(Account {
+            address,
+            chain_id: chain_id.clone(),
         })
     }
 
-    #[instrument(skip(config))]
-    pub fn simulate_new_event(config: AppConfig) { // Pass config directly or use Arc
-        info!("Starting mock event simulation task.");
-        let min_delay = config.mock_event_min_delay_secs;
-        let max_delay = config.mock_event_max_delay_secs;
-        let event_store_arc = MOCK_EVENT_STORE.clone();
+    // Lowest and highest block the account shows up in, see `AccountStore`.
+    #[instrument(skip(self))]
+    pub async fn account_activity(&self, chain_id: &ID, address: Address) -> FieldResult<Option<(u64, u64)>> {
+        Ok(self.store.account_activity(chain_id, &address.to_hex()).await?)
+    }
 
-        tokio::spawn(async move {
-            let mut rng = rand::thread_rng();
+    #[instrument(skip(self, extrinsic), fields(chain_id = %extrinsic.chain_id.as_str(), id = %extrinsic.id.as_str()))]
+    pub async fn save_extrinsic(&self, extrinsic: Extrinsic) -> Result<()> {
+        self.store.insert_extrinsic(extrinsic).await
+    }
+
+    // Persist an event and fan it out to subscribers.
+    #[instrument(skip(self, event), fields(event_id = %event.id.as_str()))]
+    pub async fn publish_event(&self, event: Event) -> Result<()> {
+        self.store.insert_event(event.clone()).await?;
+        match self.notification_sender.send(ChainNotification::Event(event)) {
+            Ok(receivers) => info!(receivers, "Broadcasted new event."),
+            // Not an error worth surfacing: it only means nobody is subscribed right now.
+            Err(_) => debug!("No active subscribers for new event."),
+        }
+        Ok(())
+    }
+
+    // Live events for a chain, optionally preceded by a replay of stored history. The
+    // broadcast receiver is opened before the replay starts so nothing published in between
+    // is missed; events seen twice are dropped by position. When the receiver lags behind the
+    // channel a `Lagged` error is yielded and the skipped range is re-read from the store,
+    // which always holds an event before it is broadcast.
+    // When blocks the subscriber already got events from are rolled back, a `Reorg` error is
+    // yielded and the events of the replacing blocks follow. Filtering on `finalized: true`
+    // delivers events only once their block is finalized, these are never rolled back.
+    #[instrument(skip(self, filter))]
+    pub async fn watch_events(
+        &self,
+        chain_id: ID,
+        filter: Option<EventFilterInput>,
+        replay: ReplayFrom,
+    ) -> Result<impl Stream<Item = Result<Event>> + Unpin> {
+        self.ensure_registered(&chain_id)?;
+        if let Some(filter) = &filter {
+            filter.validate()?;
+        }
+        let mut rx = self.notification_sender.subscribe();
+        let store = self.store.clone();
+
+        let mut position = match replay {
+            ReplayFrom::Beginning => None,
+            ReplayFrom::After(cursor) => Some(cursor),
+            // Everything stored so far counts as delivered. Only matching events count, so one
+            // skipped by a `finalized: true` filter is still delivered once it is finalized.
+            ReplayFrom::Live => {
+                let newest = EventPage { limit: 1, ..EventPage::default() };
+                store.list_events(&chain_id, filter.as_ref(), &newest).await?.first().map(EventCursor::from)
+            }
+        };
+        let mut replaying = !matches!(replay, ReplayFrom::Live);
+
+        Ok(Box::pin(async_stream::stream! {
             loop {
-                let delay_secs = rng.gen_range(min_delay..=max_delay);
-                tokio::time::sleep(tokio::time::Duration::from_secs(delay_secs)).await;
-                
-                let mut store_guard = event_store_arc.lock().unwrap(); // unwrap is fine here, panic on poison is intended for global static
-                let event_id = ID::from(Uuid::new_v4().to_string());
-                let block_num = store_guard.events.values().map(|e| e.block_number).max().unwrap_or(10000) + rng.gen_range(1..5);
-                
-                let (pallet_name, event_name, data) = if rng.gen() {
-                    ("System".to_string(), "NewAccount".to_string(), json!({ "account": Uuid::new_v4().to_string(), "balance": rng.gen_range(0..1000) }))
-                } else {
-                    ("Timestamp".to_string(), "TimestampSet".to_string(), json!({ "now": Utc::now().timestamp_millis() }))
-                };
-
-                let new_event = Event {
-                    id: event_id.clone(),
-                    block_number: block_num,
-                    extrinsic_id: Some(format!("0x{}", Uuid::new_v4().to_simple())),
-                    timestamp: Utc::now(),
-                    pallet_name: pallet_name.clone(),
-                    event_name: event_name.clone(),
-                    data,
-                    chain_id: store_guard.chain_id.clone(),
-                };
-
-                store_guard.events.insert(event_id, new_event.clone());
-                match store_guard.event_sender.send(new_event.clone()) {
-                    Ok(receivers) => info!(event_id = %new_event.id, %pallet_name, %event_name, receivers, "Simulated and broadcasted new event."),
-                    Err(e) => error!("Failed to broadcast new event: {}", e),
+                if replaying {
+                    let page = EventPage {
+                        before: position,
+                        limit: REPLAY_PAGE_SIZE,
+                        from_oldest: true,
+                        ..EventPage::default()
+                    };
+                    let events = match store.list_events(&chain_id, filter.as_ref(), &page).await {
+                        Ok(events) => events,
+                        Err(e) => {
+                            yield Err(e);
+                            break;
+                        }
+                    };
+                    replaying = events.len() == REPLAY_PAGE_SIZE;
+                    for event in events.into_iter().rev() {
+                        position = Some(EventCursor::from(&event));
+                        yield Ok(event);
+                    }
+                    continue;
+                }
+
+                match rx.recv().await {
+                    Ok(ChainNotification::Event(event)) => {
+                        if event.chain_id != chain_id || !filter.as_ref().is_none_or(|f| f.matches(&event)) {
+                            continue;
+                        }
+                        let event_position = EventCursor::from(&event);
+                        if position.is_some_and(|p| event_position <= p) {
+                            continue;
+                        }
+                        position = Some(event_position);
+                        yield Ok(event);
+                    }
+                    Ok(ChainNotification::Reorg { chain_id: reorg_chain_id, from_block, orphaned_block_hashes }) => {
+                        let retracted = EventCursor { block_number: from_block, event_index: 0 };
+                        // Nothing was delivered from the orphaned blocks
+                        if reorg_chain_id != chain_id || position.is_none_or(|p| p < retracted) {
+                            continue;
+                        }
+                        position = from_block.checked_sub(1).map(|block_number| EventCursor {
+                            block_number,
+                            event_index: u32::MAX,
+                        });
+                        yield Err(AppError::Reorg { from_block, orphaned_block_hashes });
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(chain_id = %chain_id.as_str(), skipped, "Subscriber lagged, replaying from the store");
+                        METRICS.lagged_events.with_label_values(&[chain_id.as_str()]).inc_by(skipped);
+                        yield Err(AppError::Lagged(skipped));
+                        replaying = true;
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }))
+    }
+
+    // Simulate one new block on `chain_id`, now and then replacing unfinalized blocks
+    // first, with one extrinsic and one event, and finalize behind it. Errors are logged.
+    pub async fn simulate_step(&self, chain_id: &ID) {
+        let heights = tokio::try_join!(
+            self.store.max_block_number(chain_id),
+            self.store.finalized_block_height(chain_id),
+        );
+        let (last_block, finalized_block) = match heights {
+            Ok((last, finalized)) => (last, finalized.unwrap_or(0)),
+            Err(e) => {
+                error!("Failed to read block height for mock event: {}", e);
+                return;
+            }
+        };
+
+        // Now and then fork off below the head, replacing the unfinalized blocks. An empty
+        // chain has nothing to fork from.
+        let fork_from = last_block.and_then(|last_block| {
+            let mut rng = rand::thread_rng();
+            (last_block > finalized_block && rng.gen_bool(MOCK_REORG_PROBABILITY))
+                .then(|| rng.gen_range(finalized_block + 1..=last_block))
+        });
+        let parent_block = match fork_from {
+            Some(from_block) => {
+                if let Err(e) = self.rollback(chain_id, from_block).await {
+                    error!("Failed to roll back simulated blocks: {}", e);
+                    return;
                 }
+                from_block - 1
+            }
+            None => last_block.unwrap_or(10000),
+        };
+
+        let new_block_number = parent_block + rand::thread_rng().gen_range(1..5);
+        if let Err(e) = self.simulate_blocks(chain_id, parent_block, new_block_number).await {
+            error!("Failed to store simulated blocks: {}", e);
+            return;
+        }
+
+        // Keep the thread-local rng out of scope across awaits so the task stays Send
+        let (new_extrinsic, new_event) = {
+            let mut rng = rand::thread_rng();
+            let (extrinsic, pallet_name, event_name, data) = if rng.gen() {
+                let account = format!("0x{}", hex::encode(rng.gen::<[u8; 32]>()));
+                let balance = rng.gen_range(0..1000);
+                let extrinsic = Extrinsic::mock(
+                    chain_id,
+                    new_block_number,
+                    0,
+                    Some(ALICE),
+                    ("Balances", "transfer_allow_death"),
+                    json!({ "dest": account, "value": balance }),
+                );
+                (extrinsic, "System".to_string(), "NewAccount".to_string(), json!({ "account": account, "balance": balance }))
+            } else {
+                let now = Utc::now().timestamp_millis();
+                let extrinsic = Extrinsic::mock(chain_id, new_block_number, 0, None, ("Timestamp", "set"), json!({ "now": now }));
+                (extrinsic, "Timestamp".to_string(), "TimestampSet".to_string(), json!({ "now": now }))
+            };
+            let event = Event {
+                id: ID::from(Uuid::new_v4().to_string()),
+                block_number: new_block_number,
+                event_index: 0,
+                extrinsic_id: Some(extrinsic.id.to_string()),
+                timestamp: Utc::now(),
+                pallet_name,
+                event_name,
+                data,
+                finalized: false,
+                chain_id: chain_id.clone(),
+            };
+            (Extrinsic { finalized: false, ..extrinsic }, event)
+        };
+
+        if let Err(e) = self.save_extrinsic(new_extrinsic).await {
+            error!("Failed to store simulated extrinsic: {}", e);
+            return;
+        }
+        info!(chain_id = %chain_id.as_str(), pallet_name = %new_event.pallet_name, event_name = %new_event.event_name, "Simulated new event.");
+        if let Err(e) = self.publish_event(new_event).await {
+            error!("Failed to store simulated event: {}", e);
+            return;
+        }
+        if let Err(e) = self.finalize(chain_id, new_block_number.saturating_sub(MOCK_FINALITY_LAG)).await {
+            error!("Failed to finalize simulated blocks: {}", e);
+        }
+    }
+
+    // Unfinalized mock blocks `from + 1..=to`, chained onto the stored block `from` when there is one.
+    async fn simulate_blocks(&self, chain_id: &ID, from: u64, to: u64) -> Result<()> {
+        let mut parent_hash = match self.store.get_block_by_number(chain_id, from).await? {
+            Some(parent) => parent.hash,
+            None => format!("0x{}", "00".repeat(32)),
+        };
+        for number in from + 1..=to {
+            let block = Block {
+                finalized: false,
+                ..Block::mock(chain_id, number, parent_hash, Utc::now())
+            };
+            parent_hash = block.hash.clone();
+            self.save_block(block).await?;
+        }
+        Ok(())
+    }
+}
+
+// Drop the extra row fetched past the end of `page` and report (has_previous_page,
+// has_next_page). Items are sorted newest first.
+fn trim_page<T, K>(items: &mut Vec<T>, page: &Page<K>) -> (bool, bool) {
+    let has_more = items.len() > page.limit;
+    if has_more {
+        if page.from_oldest {
+            items.remove(0);
+        } else {
+            items.truncate(page.limit);
+        }
+    }
+    if page.from_oldest {
+        (has_more, page.before.is_some())
+    } else {
+        (page.after.is_some(), has_more)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use tokio_stream::StreamExt;
+
+    fn service() -> SubstrateIndexerService {
+        let chain = ChainConfig {
+            id: "dev".to_string(),
+            name: "Dev".to_string(),
+            endpoint: "ws://127.0.0.1:9944".to_string(),
+            replay_file: None,
+            backfill_from_block: None,
+            ss58_prefix: 42,
+            token_symbol: "UNIT".to_string(),
+            decimals: 12,
+        };
+        SubstrateIndexerService::new(AppConfig::for_chains(vec![chain]), Arc::new(MemoryStore::default()))
+    }
+
+    // The `n`th event of the chain, two per block
+    fn event(n: u64) -> Event {
+        Event {
+            id: ID::from(format!("dev-{}", n)),
+            block_number: n / 2 + 1,
+            event_index: (n % 2) as u32,
+            extrinsic_id: None,
+            timestamp: Utc::now(),
+            pallet_name: "System".to_string(),
+            event_name: "Remarked".to_string(),
+            data: json!({ "n": n }),
+            finalized: false,
+            chain_id: ID::from("dev"),
+        }
+    }
+
+    async fn next_id(events: &mut (impl Stream<Item = Result<Event>> + Unpin)) -> String {
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), events.next())
+            .await
+            .expect("no event within 5s")
+            .expect("stream ended")
+            .expect("stream yielded an error");
+        event.id.to_string()
+    }
+
+    // Events published while stored history is replayed come after it, each exactly once
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn replay_after_cursor_then_live_without_gaps_or_duplicates() {
+        let service = service();
+        let stored = REPLAY_PAGE_SIZE as u64 + 100; // More than one replay page
+        for n in 0..stored {
+            service.publish_event(event(n)).await.unwrap();
+        }
+
+        let cursor = EventCursor::from(&event(9));
+        let mut events = service.watch_events(ID::from("dev"), None, ReplayFrom::After(cursor)).await.unwrap();
+        assert_eq!(next_id(&mut events).await, "dev-10");
+
+        let live = (NOTIFICATION_CAPACITY / 2) as u64;
+        let publisher = service.clone();
+        let publishing = tokio::spawn(async move {
+            for n in stored..stored + live {
+                publisher.publish_event(event(n)).await.unwrap();
+                tokio::task::yield_now().await;
             }
         });
+
+        for n in 11..stored + live {
+            assert_eq!(next_id(&mut events).await, format!("dev-{}", n));
+        }
+        publishing.await.unwrap();
+        service.publish_event(event(stored + live)).await.unwrap();
+        assert_eq!(next_id(&mut events).await, format!("dev-{}", stored + live));
     }
-} 
\ No newline at end of file
+
+    // A subscriber that falls behind the channel is told so, then catches up from the store
+    #[tokio::test]
+    async fn lagging_subscriber_gets_missed_events_from_the_store() {
+        let service = service();
+        let mut events = service.watch_events(ID::from("dev"), None, ReplayFrom::Live).await.unwrap();
+
+        // The channel rounds its capacity up to a power of two
+        let published = NOTIFICATION_CAPACITY.next_power_of_two() as u64 + 20;
+        for n in 0..published {
+            service.publish_event(event(n)).await.unwrap();
+        }
+
+        match events.next().await {
+            Some(Err(e)) => assert_eq!(e.code(), "SUBSCRIPTION_LAGGED"),
+            other => panic!("expected a lag error, got {:?}", other),
+        }
+        for n in 0..published {
+            assert_eq!(next_id(&mut events).await, format!("dev-{}", n));
+        }
+        // The notifications still buffered were all delivered from the store already
+        let duplicate = tokio::time::timeout(std::time::Duration::from_millis(100), events.next()).await;
+        assert!(duplicate.is_err(), "unexpected {:?}", duplicate);
+        service.publish_event(event(published)).await.unwrap();
+        assert_eq!(next_id(&mut events).await, format!("dev-{}", published));
+    }
+}
@@ -0,0 +1,247 @@
+use super::rpc::RpcClient;
+use super::{fetch_block, Endpoints, MetadataCache};
+use crate::config::{BackfillConfig, ChainConfig};
+use crate::errors::{AppError, Result};
+use crate::indexer::SubstrateIndexerService;
+use crate::models::{BackfillState, BackfillStatus};
+use crate::store::IndexedBlock;
+use async_graphql::ID;
+use chrono::Utc;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use tracing::{error, info, instrument};
+
+// Indexes historical blocks of a chain, at most one backfill per chain at a time. Blocks
+// are fetched in batches of `batch_size`, up to `parallelism` of them concurrently, and
+// the store's checkpoint is advanced after each batch so an interrupted backfill resumes
+// where it stopped. Only finalized blocks are backfilled, so live ingestion and the
+// backfill never disagree about a block.
+#[derive(Clone)]
+pub struct Backfiller {
+    service: SubstrateIndexerService,
+    config: BackfillConfig,
+    endpoints: Endpoints,
+    // Claimed (`None`) before a backfill's checkpoint is first written, so two concurrent
+    // starts cannot both write one
+    running: Arc<Mutex<HashMap<ID, Option<AbortHandle>>>>,
+}
+
+impl Backfiller {
+    pub fn new(service: SubstrateIndexerService, config: BackfillConfig, endpoints: Endpoints) -> Self {
+        Self {
+            service,
+            config,
+            endpoints,
+            running: Arc::default(),
+        }
+    }
+
+    // Resume every unfinished backfill that was not cancelled and start the ones configured
+    // with `backfill_from_block` that never ran. Failures are logged per chain.
+    pub async fn start_configured(&self, chains: &[ChainConfig]) {
+        for chain in chains {
+            let chain_id = ID::from(chain.id.as_str());
+            let result = match self.service.store().get_backfill(&chain_id).await {
+                Ok(Some(status)) if matches!(status.state, BackfillState::Running | BackfillState::Failed) => {
+                    info!(chain_id = %chain.id, next_block = status.next_block, to_block = status.to_block, "Resuming backfill");
+                    self.resume(status)
+                }
+                Ok(Some(_)) => Ok(()),
+                Ok(None) => match chain.backfill_from_block {
+                    Some(from_block) => self.start(&chain_id, from_block, None).await.map(|_| ()),
+                    None => Ok(()),
+                },
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error!(chain_id = %chain.id, "Failed to start backfill: {}", e);
+            }
+        }
+    }
+
+    // Backfill `from_block..=to_block`, up to the finalized head when `to_block` is `None`,
+    // which it must not be past. Replaces the chain's previous backfill, which must not be
+    // running anymore.
+    pub async fn start(&self, chain_id: &ID, from_block: u64, to_block: Option<u64>) -> Result<BackfillStatus> {
+        self.claim(chain_id)?;
+        let result = self.start_claimed(chain_id, from_block, to_block).await;
+        if result.is_err() {
+            self.release(chain_id);
+        }
+        result
+    }
+
+    async fn start_claimed(&self, chain_id: &ID, from_block: u64, to_block: Option<u64>) -> Result<BackfillStatus> {
+        let client = RpcClient::connect(&self.endpoint(chain_id).await?).await?;
+        let finalized = finalized_height(&client).await?;
+        let to_block = to_block.unwrap_or(finalized);
+        // Backfilled blocks are stored as finalized, a reorg must not be able to replace one
+        if to_block > finalized {
+            return Err(AppError::InvalidInput(format!(
+                "Backfill cannot go past the finalized block {}, got {}",
+                finalized, to_block
+            )));
+        }
+        if from_block > to_block {
+            return Err(AppError::InvalidInput(format!(
+                "Backfill range {}..={} is empty",
+                from_block, to_block
+            )));
+        }
+        let now = Utc::now();
+        let status = BackfillStatus {
+            from_block,
+            to_block,
+            next_block: from_block,
+            state: BackfillState::Running,
+            error: None,
+            started_at: now,
+            updated_at: now,
+            chain_id: chain_id.clone(),
+        };
+        self.service.store().save_backfill(status.clone()).await?;
+        info!(chain_id = %chain_id.as_str(), from_block, to_block, "Starting backfill");
+        self.spawn(status.clone())?;
+        Ok(status)
+    }
+
+    // Abort the chain's running backfill, if any. It keeps its last checkpoint and is not
+    // resumed on restart.
+    pub async fn cancel(&self, chain_id: &ID) -> Result<()> {
+        let Some(task) = self.running()?.remove(chain_id) else {
+            return Ok(());
+        };
+        if let Some(task) = task {
+            task.abort();
+        }
+        if let Some(mut status) = self.service.store().get_backfill(chain_id).await? {
+            status.state = BackfillState::Cancelled;
+            status.error = None;
+            status.updated_at = Utc::now();
+            self.service.store().save_backfill(status).await?;
+        }
+        info!(chain_id = %chain_id.as_str(), "Cancelled backfill");
+        Ok(())
+    }
+
+    fn resume(&self, status: BackfillStatus) -> Result<()> {
+        self.claim(&status.chain_id)?;
+        self.spawn(status)
+    }
+
+    async fn endpoint(&self, chain_id: &ID) -> Result<String> {
+        self.endpoints.resolve(&self.service.chain(chain_id)?).await
+    }
+
+    fn running(&self) -> Result<MutexGuard<'_, HashMap<ID, Option<AbortHandle>>>> {
+        self.running
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock backfill registry: {}", e)))
+    }
+
+    // Reserve the chain's slot, taken over by `spawn`
+    fn claim(&self, chain_id: &ID) -> Result<()> {
+        let mut running = self.running()?;
+        if running.contains_key(chain_id) {
+            return Err(already_running(chain_id));
+        }
+        running.insert(chain_id.clone(), None);
+        Ok(())
+    }
+
+    fn release(&self, chain_id: &ID) {
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(chain_id);
+        }
+    }
+
+    // Run the claimed backfill until it completes, retrying from the last checkpoint on errors.
+    fn spawn(&self, mut status: BackfillStatus) -> Result<()> {
+        let mut running = self.running()?;
+        let chain_id = status.chain_id.clone();
+        let backfiller = self.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                match backfiller.run(&mut status).await {
+                    Ok(()) => break,
+                    Err(e) => {
+                        error!(chain_id = %status.chain_id.as_str(), next_block = status.next_block, "Backfill failed: {}", e);
+                        status.state = BackfillState::Failed;
+                        status.error = Some(e.to_string());
+                        status.updated_at = Utc::now();
+                        if let Err(e) = backfiller.service.store().save_backfill(status.clone()).await {
+                            error!(chain_id = %status.chain_id.as_str(), "Failed to save backfill status: {}", e);
+                        }
+                    }
+                }
+                tokio::time::sleep(Duration::from_secs(backfiller.config.retry_delay_secs)).await;
+                status.state = BackfillState::Running;
+                status.error = None;
+            }
+            if let Ok(mut running) = backfiller.running.lock() {
+                running.remove(&status.chain_id);
+            }
+        });
+        running.insert(chain_id, Some(task.abort_handle()));
+        Ok(())
+    }
+
+    #[instrument(skip(self, status), fields(chain_id = %status.chain_id.as_str()))]
+    async fn run(&self, status: &mut BackfillStatus) -> Result<()> {
+        let client = RpcClient::connect(&self.endpoint(&status.chain_id).await?).await?;
+        let metadata = MetadataCache::new(self.service.clone(), status.chain_id.clone());
+        let store = self.service.store();
+        let chain_id = status.chain_id.clone();
+
+        while status.next_block <= status.to_block {
+            let batch_end = status
+                .to_block
+                .min(status.next_block + self.config.batch_size.max(1) - 1);
+            // `buffered` keeps the batch in block order while fetching concurrently
+            let blocks: Vec<IndexedBlock> = stream::iter(status.next_block..=batch_end)
+                .map(|number| fetch_finalized_block(&client, &metadata, &chain_id, number))
+                .buffered(self.config.parallelism.max(1))
+                .try_collect()
+                .await?;
+            // Written to the store directly: these events are history, not news for subscribers
+            let mut checkpoint = status.clone();
+            checkpoint.next_block = batch_end + 1;
+            checkpoint.updated_at = Utc::now();
+            store.save_backfill_batch(blocks, checkpoint.clone()).await?;
+            *status = checkpoint;
+            info!(next_block = status.next_block, to_block = status.to_block, "Backfill checkpoint");
+        }
+
+        status.state = BackfillState::Completed;
+        store.save_backfill(status.clone()).await?;
+        info!(from_block = status.from_block, to_block = status.to_block, "Backfill completed");
+        Ok(())
+    }
+}
+
+fn already_running(chain_id: &ID) -> AppError {
+    AppError::InvalidInput(format!("A backfill is already running for chain: {}", chain_id.as_str()))
+}
+
+// The node's canonical block at `number`, stored as finalized.
+async fn fetch_finalized_block(client: &RpcClient, metadata: &MetadataCache, chain_id: &ID, number: u64) -> Result<IndexedBlock> {
+    let hash = client
+        .block_hash(number)
+        .await?
+        .ok_or_else(|| AppError::Rpc(format!("Node has no block at {}", number)))?;
+    fetch_block(client, metadata, chain_id, number, &hash, true).await
+}
+
+async fn finalized_height(client: &RpcClient) -> Result<u64> {
+    let hash = client.finalized_head().await?;
+    client
+        .block(&hash)
+        .await?
+        .ok_or_else(|| AppError::Rpc(format!("Node has no body for block {}", hash)))?
+        .block
+        .header
+        .block_number()
+}
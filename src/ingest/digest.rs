@@ -0,0 +1,50 @@
+use parity_scale_codec::{Decode, Input};
+
+// `DigestItem::PreRuntime`, the consensus engine's claim to the slot
+const PRE_RUNTIME_DIGEST: u8 = 6;
+const BABE_ENGINE_ID: [u8; 4] = *b"BABE";
+const AURA_ENGINE_ID: [u8; 4] = *b"aura";
+
+// What the header says about its author. Resolving it to an account needs the
+// session's validator set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorClaim {
+    // BABE names the authority directly
+    AuthorityIndex(u32),
+    // Aura authorities take turns, the author is `slot % authorities`
+    Slot(u64),
+}
+
+impl AuthorClaim {
+    pub fn resolve<T>(self, authorities: &[T]) -> Option<&T> {
+        let index = match self {
+            AuthorClaim::AuthorityIndex(index) => index as usize,
+            AuthorClaim::Slot(slot) if !authorities.is_empty() => (slot % authorities.len() as u64) as usize,
+            AuthorClaim::Slot(_) => return None,
+        };
+        authorities.get(index)
+    }
+}
+
+// Find the author claim among a header's hex encoded digest logs. Unknown engines
+// and malformed entries are ignored.
+pub fn author_claim(logs: &[String]) -> Option<AuthorClaim> {
+    logs.iter().find_map(|log| {
+        let bytes = hex::decode(log.trim_start_matches("0x")).ok()?;
+        let input = &mut &bytes[..];
+        if input.read_byte().ok()? != PRE_RUNTIME_DIGEST {
+            return None;
+        }
+        let (engine, payload) = <([u8; 4], Vec<u8>)>::decode(input).ok()?;
+        let payload = &mut &payload[..];
+        match engine {
+            // PreDigest::{Primary, SecondaryPlain, SecondaryVRF} all start with the authority index
+            BABE_ENGINE_ID => {
+                payload.read_byte().ok()?;
+                u32::decode(payload).ok().map(AuthorClaim::AuthorityIndex)
+            }
+            AURA_ENGINE_ID => u64::decode(payload).ok().map(AuthorClaim::Slot),
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,86 @@
+use crate::errors::{AppError, Result};
+use crate::metadata::{value_to_json, DecodedMetadata};
+use scale_info::PortableRegistry;
+use scale_value::{Composite, Primitive, Value, ValueDef};
+use serde_json::{Map, Value as JsonValue};
+
+// One entry of `System.Events`, flattened for storage.
+#[derive(Debug, Clone)]
+pub struct DecodedEvent {
+    pub index: u32,
+    // Index of the extrinsic that emitted the event, `None` for initialization/finalization events
+    pub extrinsic_index: Option<u32>,
+    pub pallet_name: String,
+    pub event_name: String,
+    pub data: JsonValue,
+}
+
+// Decode the raw `System.Events` storage value of a block.
+pub fn decode_events(metadata: &DecodedMetadata, bytes: &[u8]) -> Result<Vec<DecodedEvent>> {
+    let records = scale_value::scale::decode_as_type(&mut &bytes[..], metadata.events_type, &metadata.types)
+        .map_err(|e| AppError::Decode(format!("Failed to decode System.Events: {}", e)))?;
+
+    let ValueDef::Composite(records) = records.value else {
+        return Err(AppError::Decode("System.Events is not a sequence".to_string()));
+    };
+
+    records
+        .into_values()
+        .enumerate()
+        .map(|(index, record)| decode_record(&metadata.types, index as u32, record))
+        .collect()
+}
+
+fn decode_record(types: &PortableRegistry, index: u32, record: Value<u32>) -> Result<DecodedEvent> {
+    let malformed = || AppError::Decode(format!("Malformed EventRecord at index {}", index));
+    let ValueDef::Composite(Composite::Named(fields)) = record.value else {
+        return Err(malformed());
+    };
+
+    let mut phase = None;
+    let mut event = None;
+    for (name, value) in fields {
+        match name.as_str() {
+            "phase" => phase = Some(value),
+            "event" => event = Some(value),
+            _ => {}
+        }
+    }
+
+    let extrinsic_index = match phase.map(|p| p.value) {
+        Some(ValueDef::Variant(v)) if v.name == "ApplyExtrinsic" => v.values.into_values().next().and_then(|idx| match idx.value {
+            ValueDef::Primitive(Primitive::U128(idx)) => u32::try_from(idx).ok(),
+            _ => None,
+        }),
+        _ => None,
+    };
+
+    // RuntimeEvent::<Pallet>(<Pallet>Event::<Variant> { .. })
+    let Some(ValueDef::Variant(pallet)) = event.map(|e| e.value) else {
+        return Err(malformed());
+    };
+    let Some(ValueDef::Variant(inner)) = pallet.values.into_values().next().map(|v| v.value) else {
+        return Err(malformed());
+    };
+
+    Ok(DecodedEvent {
+        index,
+        extrinsic_index,
+        pallet_name: pallet.name,
+        event_name: inner.name,
+        data: fields_to_json(types, &inner.values),
+    })
+}
+
+// Fields of an event or call variant: an object when named, an array otherwise.
+pub fn fields_to_json(types: &PortableRegistry, fields: &Composite<u32>) -> JsonValue {
+    match fields {
+        Composite::Named(fields) => JsonValue::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value_to_json(types, value)))
+                .collect::<Map<_, _>>(),
+        ),
+        Composite::Unnamed(values) => JsonValue::Array(values.iter().map(|v| value_to_json(types, v)).collect()),
+    }
+}
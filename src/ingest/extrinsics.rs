@@ -0,0 +1,117 @@
+use super::events::fields_to_json;
+use super::rpc::decode_hex;
+use crate::errors::{AppError, Result};
+use crate::metadata::{value_to_json, DecodedMetadata, ExtrinsicTypes};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use parity_scale_codec::{Compact, Decode, Input};
+use scale_value::{Value, ValueDef};
+use serde_json::Value as JsonValue;
+
+// Set on the version byte of signed extrinsics
+const SIGNED_FLAG: u8 = 0b1000_0000;
+
+// One extrinsic of a block body, decoded against the runtime metadata.
+#[derive(Debug, Clone)]
+pub struct DecodedExtrinsic {
+    pub index: u32,
+    pub hash: String,
+    pub signer: Option<String>,
+    pub nonce: Option<u64>,
+    pub tip: Option<String>,
+    pub pallet_name: String,
+    pub call_name: String,
+    pub args: JsonValue,
+}
+
+// Decode the hex encoded extrinsics of a `chain_getBlock` body.
+pub fn decode_extrinsics(metadata: &DecodedMetadata, extrinsics: &[String]) -> Result<Vec<DecodedExtrinsic>> {
+    let types = metadata
+        .extrinsic
+        .as_ref()
+        .ok_or_else(|| AppError::Decode("Runtime metadata does not describe the extrinsic format".to_string()))?;
+    extrinsics
+        .iter()
+        .enumerate()
+        .map(|(index, extrinsic)| decode_extrinsic(metadata, types, index as u32, extrinsic))
+        .collect()
+}
+
+fn decode_extrinsic(metadata: &DecodedMetadata, types: &ExtrinsicTypes, index: u32, extrinsic: &str) -> Result<DecodedExtrinsic> {
+    let malformed = |reason: String| AppError::Decode(format!("Malformed extrinsic at index {}: {}", index, reason));
+    let decode = |input: &mut &[u8], ty: u32| {
+        scale_value::scale::decode_as_type(input, ty, &metadata.types).map_err(|e| malformed(e.to_string()))
+    };
+
+    let bytes = decode_hex(extrinsic)?;
+    // The hash covers the whole encoding, length prefix included
+    let hash = format!("0x{}", hex::encode(Blake2b::<U32>::digest(&bytes)));
+
+    let input = &mut &bytes[..];
+    Compact::<u32>::decode(input).map_err(|e| malformed(e.to_string()))?;
+    let version = input.read_byte().map_err(|e| malformed(e.to_string()))?;
+    if version & !SIGNED_FLAG != types.version {
+        return Err(malformed(format!("unsupported extrinsic version {}", version & !SIGNED_FLAG)));
+    }
+
+    let (mut signer, mut nonce, mut tip) = (None, None, None);
+    if version & SIGNED_FLAG != 0 {
+        let address = decode(input, types.address)?;
+        signer = Some(address_to_string(value_to_json(&metadata.types, &address)));
+        decode(input, types.signature)?;
+        for (identifier, ty) in &types.signed_extensions {
+            let value = value_to_json(&metadata.types, &decode(input, *ty)?);
+            match identifier.as_str() {
+                "CheckNonce" => nonce = integer_to_string(&value).and_then(|n| n.parse().ok()),
+                "ChargeTransactionPayment" => tip = integer_to_string(&value),
+                "ChargeAssetTxPayment" => tip = value.get("tip").and_then(integer_to_string),
+                _ => {}
+            }
+        }
+    }
+
+    // RuntimeCall::<Pallet>(<Pallet>Call::<call> { .. })
+    let call: Value<u32> = decode(input, types.call)?;
+    if !input.is_empty() {
+        return Err(malformed(format!("{} trailing bytes", input.len())));
+    }
+    let ValueDef::Variant(pallet) = call.value else {
+        return Err(malformed("call is not an enum".to_string()));
+    };
+    let Some(ValueDef::Variant(inner)) = pallet.values.into_values().next().map(|v| v.value) else {
+        return Err(malformed(format!("{} call is not an enum", pallet.name)));
+    };
+
+    Ok(DecodedExtrinsic {
+        index,
+        hash,
+        signer,
+        nonce,
+        tip,
+        pallet_name: pallet.name,
+        call_name: inner.name,
+        args: fields_to_json(&metadata.types, &inner.values),
+    })
+}
+
+// `MultiAddress::Id(account)` and plain account ids become the 0x-prefixed account,
+// anything else is kept as its JSON rendering.
+fn address_to_string(address: JsonValue) -> String {
+    match address {
+        JsonValue::String(account) => account,
+        JsonValue::Object(ref variant) => match variant.get("Id") {
+            Some(JsonValue::String(account)) => account.clone(),
+            _ => address.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+// Balances come out of `value_to_json` as numbers or, past u64, decimal strings.
+pub fn integer_to_string(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Number(n) if n.is_u64() => Some(n.to_string()),
+        JsonValue::String(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => Some(s.clone()),
+        _ => None,
+    }
+}
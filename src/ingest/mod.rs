@@ -0,0 +1,647 @@
+use crate::config::{ChainConfig, IngestionConfig};
+use crate::errors::{AppError, Result};
+use crate::indexer::SubstrateIndexerService;
+use crate::metadata::DecodedMetadata;
+use crate::models::{Block, ChainInfo, Event, Extrinsic, WorkerState};
+use crate::store::IndexedBlock;
+use crate::workers::wait_until_running;
+use async_graphql::ID;
+use chrono::{DateTime, Utc};
+use parity_scale_codec::Decode;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument, warn};
+
+mod backfill;
+mod digest;
+mod events;
+mod extrinsics;
+mod replay;
+mod rpc;
+
+use replay::{MockRpcServer, Recording};
+use rpc::{RpcClient, RpcHeader};
+
+pub use backfill::Backfiller;
+
+// twox128("System") ++ twox128("Events")
+const SYSTEM_EVENTS_KEY: &str = "0x26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7";
+// twox128("Timestamp") ++ twox128("Now")
+const TIMESTAMP_NOW_KEY: &str = "0xf0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb";
+// twox128("Session") ++ twox128("Validators")
+const SESSION_VALIDATORS_KEY: &str = "0xcec5070d609dd3497f72bde07fc96ba088dcde934c658227ee1dfafcd6e16903";
+
+// Longest branch followed back from a new head before giving up on it
+const MAX_REORG_DEPTH: usize = 256;
+
+// Follows the best and finalized heads of one chain's Substrate node and feeds decoded
+// blocks into the service, which persists them and fans their events out to subscribers.
+// Blocks are stored unfinalized until a finalized head covers them; when the best chain
+// switches branches the stored blocks of the abandoned one are rolled back.
+pub struct Ingestor {
+    service: SubstrateIndexerService,
+    chain_id: ID,
+    config: IngestionConfig,
+    metadata: MetadataCache,
+    // Head of the best chain as stored
+    last_ingested: Option<u64>,
+    last_finalized: Option<u64>,
+}
+
+impl Ingestor {
+    pub fn new(service: SubstrateIndexerService, chain_id: ID, config: IngestionConfig) -> Self {
+        Self {
+            metadata: MetadataCache::new(service.clone(), chain_id.clone()),
+            service,
+            chain_id,
+            config,
+            last_ingested: None,
+            last_finalized: None,
+        }
+    }
+
+    // Spawn the ingestion loop for the chain behind `endpoint`, see `Endpoints`. It
+    // disconnects while `control` is paused, picking up where it left off once running
+    // again, and ends when stopped.
+    pub fn spawn(
+        service: SubstrateIndexerService,
+        chain_id: ID,
+        endpoint: String,
+        config: IngestionConfig,
+        mut control: watch::Receiver<WorkerState>,
+    ) -> JoinHandle<()> {
+        let mut ingestor = Self::new(service, chain_id, config);
+        tokio::spawn(async move {
+            while wait_until_running(&mut control).await {
+                match ingestor.run(&endpoint, &mut control).await {
+                    Ok(()) if *control.borrow() != WorkerState::Running => continue,
+                    Ok(()) => warn!(chain_id = %ingestor.chain_id.as_str(), "Head subscription ended"),
+                    Err(e) => error!(chain_id = %ingestor.chain_id.as_str(), "Ingestion failed: {}", e),
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(ingestor.config.reconnect_delay_secs)) => {}
+                    _ = control.changed() => continue,
+                }
+                info!(chain_id = %ingestor.chain_id.as_str(), endpoint = %endpoint, "Reconnecting to Substrate node");
+            }
+            info!(chain_id = %ingestor.chain_id.as_str(), "Ingestion stopped");
+        })
+    }
+
+    // Follow the node until the subscriptions end or `control` leaves `Running`.
+    #[instrument(skip(self, control), fields(chain_id = %self.chain_id.as_str()))]
+    async fn run(&mut self, endpoint: &str, control: &mut watch::Receiver<WorkerState>) -> Result<()> {
+        let client = RpcClient::connect(endpoint).await?;
+        info!("Connected to Substrate node");
+        self.sync_chain_info(&client).await?;
+
+        if self.last_ingested.is_none() {
+            // Unfinalized blocks left by a previous run may have been orphaned since
+            if let Some(finalized) = self.service.store().finalized_block_height(&self.chain_id).await? {
+                self.service.rollback(&self.chain_id, finalized + 1).await?;
+                self.last_finalized = Some(finalized);
+            }
+        }
+
+        let mut best_heads = client.subscribe_new_heads().await?;
+        let mut finalized_heads = client.subscribe_finalized_heads().await?;
+        loop {
+            // Control first, then best heads: a finalized head then usually finds its block
+            // stored already
+            tokio::select! {
+                biased;
+                changed = control.changed() => {
+                    if changed.is_err() || *control.borrow() != WorkerState::Running {
+                        info!("Ingestion halted");
+                        break;
+                    }
+                }
+                header = best_heads.next::<RpcHeader>() => match header {
+                    Some(header) => {
+                        let header = header?;
+                        if let Ok(number) = header.block_number() {
+                            self.service.record_node_head(&self.chain_id, number);
+                        }
+                        // Late notification for a block that is final by now
+                        if self.last_finalized.is_some_and(|f| header.block_number().is_ok_and(|n| n <= f)) {
+                            continue;
+                        }
+                        self.follow_head(&client, header).await?;
+                    }
+                    None => break,
+                },
+                header = finalized_heads.next::<RpcHeader>() => match header {
+                    Some(header) => {
+                        let header = header?;
+                        let number = header.block_number()?;
+                        if self.last_finalized.is_some_and(|f| number <= f) {
+                            continue;
+                        }
+                        self.follow_head(&client, header).await?;
+                        self.service.finalize(&self.chain_id, number).await?;
+                        self.last_finalized = Some(number);
+                    }
+                    None => break,
+                },
+            }
+        }
+        Ok(())
+    }
+
+    // Make `head` the stored head. Its ancestors are fetched back to the first one stored
+    // with the same hash, filling gaps and replacing blocks of an abandoned branch, which
+    // are rolled back first. On a fresh start only the head itself is ingested, history
+    // is the backfill's job.
+    async fn follow_head(&mut self, client: &RpcClient, head: RpcHeader) -> Result<()> {
+        let store = self.service.store();
+        let head_number = head.block_number()?;
+        let (mut number, mut hash, mut parent_hash) = (head_number, head.hash()?, head.parent_hash);
+        let mut branch = Vec::new(); // Newest first
+        let mut orphaned_from = None;
+        loop {
+            match store.get_block_by_number(&self.chain_id, number).await? {
+                Some(stored) if stored.hash == hash => break,
+                Some(stored) if stored.finalized => {
+                    return Err(AppError::Rpc(format!(
+                        "Block {} conflicts with finalized block {} at {}",
+                        hash, stored.hash, number
+                    )));
+                }
+                Some(_) => orphaned_from = Some(number),
+                // Below the stored head and not stored: a gap this session never covered
+                None if !branch.is_empty() && self.last_ingested.is_none_or(|last| number <= last) => break,
+                None => {}
+            }
+            branch.push((number, hash));
+            if self.last_ingested.is_none() || number == 0 {
+                break;
+            }
+            if branch.len() >= MAX_REORG_DEPTH {
+                return Err(AppError::Rpc(format!("No known ancestor within {} blocks of head {}", MAX_REORG_DEPTH, head_number)));
+            }
+            let parent = client
+                .block(&parent_hash)
+                .await?
+                .ok_or_else(|| AppError::Rpc(format!("Node has no body for block {}", parent_hash)))?
+                .block
+                .header;
+            number -= 1;
+            hash = std::mem::replace(&mut parent_hash, parent.parent_hash);
+        }
+
+        if let Some(from_block) = orphaned_from {
+            self.service.rollback(&self.chain_id, from_block).await?;
+        }
+        for (number, hash) in branch.into_iter().rev() {
+            self.ingest_block(client, number, &hash).await?;
+        }
+        self.last_ingested = match (orphaned_from, self.last_ingested) {
+            (None, Some(last)) => Some(last.max(head_number)),
+            _ => Some(head_number),
+        };
+        Ok(())
+    }
+
+    // Refresh the ChainInfo row from the node's own description of itself.
+    async fn sync_chain_info(&self, client: &RpcClient) -> Result<()> {
+        let name = client.system_chain().await?;
+        let version = client.runtime_version(None).await?;
+        let properties = client.system_properties().await?;
+
+        let store = self.service.store();
+        let chain_id = &self.chain_id;
+        let current = store
+            .get_chain_info(chain_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("ChainInfo not found for ID: {}", chain_id.as_str())))?;
+        let chain_info = ChainInfo {
+            id: current.id.clone(),
+            name,
+            version: format!("{}-{}", version.spec_name, version.spec_version),
+            token_symbol: first_property(&properties, "tokenSymbol")
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or(current.token_symbol),
+            decimals: first_property(&properties, "tokenDecimals")
+                .and_then(Value::as_u64)
+                .and_then(|d| u8::try_from(d).ok())
+                .unwrap_or(current.decimals),
+            ssv58_prefix: properties
+                .get("ss58Format")
+                .and_then(Value::as_u64)
+                .and_then(|p| u16::try_from(p).ok())
+                .unwrap_or(current.ssv58_prefix),
+            last_updated: Utc::now(),
+        };
+        info!(chain = %chain_info.name, version = %chain_info.version, "Synced chain info from node");
+        store.upsert_chain_info(chain_info).await
+    }
+
+    #[instrument(skip(self, client), fields(chain_id = %self.chain_id.as_str()))]
+    async fn ingest_block(&self, client: &RpcClient, number: u64, hash: &str) -> Result<()> {
+        // Unfinalized until `SubstrateIndexerService::finalize` covers it
+        let block = fetch_block(client, &self.metadata, &self.chain_id, number, hash, false).await?;
+        info!(number, %hash, extrinsics = block.extrinsics.len(), events = block.events.len(), "Ingesting block");
+        // Stored before its events so `Event.block` resolves as soon as they are published
+        self.service.save_block(block.block).await?;
+        for extrinsic in block.extrinsics {
+            self.service.save_extrinsic(extrinsic).await?;
+        }
+        for event in block.events {
+            self.service.publish_event(event).await?;
+        }
+        Ok(())
+    }
+}
+
+// The RPC endpoints chains are ingested and backfilled from, resolved on first use.
+// Clones share the resolved endpoints.
+#[derive(Clone, Default)]
+pub struct Endpoints {
+    resolved: Arc<tokio::sync::Mutex<HashMap<ID, String>>>,
+}
+
+impl Endpoints {
+    // The chain's `endpoint`, unless its `replay_file` is configured: then a local mock
+    // RPC server replaying that recording is started, once, and used instead.
+    pub async fn resolve(&self, chain: &ChainConfig) -> Result<String> {
+        let mut resolved = self.resolved.lock().await;
+        let chain_id = ID::from(chain.id.as_str());
+        if let Some(endpoint) = resolved.get(&chain_id) {
+            return Ok(endpoint.clone());
+        }
+        let endpoint = match &chain.replay_file {
+            Some(path) => {
+                let server = MockRpcServer::start(Recording::from_file(path)?).await?;
+                info!(chain_id = %chain.id, path = %path, url = %server.url(), "Replaying recorded RPC responses");
+                server.url()
+            }
+            None => chain.endpoint.clone(),
+        };
+        resolved.insert(chain_id, endpoint.clone());
+        Ok(endpoint)
+    }
+
+    pub async fn forget(&self, chain_id: &ID) {
+        self.resolved.lock().await.remove(chain_id);
+    }
+}
+
+// Decoded runtime metadata of one chain by spec version. Clones share the cache, so
+// concurrent fetches decode each runtime's metadata once.
+#[derive(Clone)]
+struct MetadataCache {
+    service: SubstrateIndexerService,
+    chain_id: ID,
+    versions: Arc<tokio::sync::Mutex<HashMap<u32, Arc<DecodedMetadata>>>>,
+}
+
+impl MetadataCache {
+    fn new(service: SubstrateIndexerService, chain_id: ID) -> Self {
+        Self {
+            service,
+            chain_id,
+            versions: Arc::default(),
+        }
+    }
+
+    // Metadata valid at `hash`, fetched the first time its runtime version is seen.
+    async fn at(&self, client: &RpcClient, hash: &str) -> Result<Arc<DecodedMetadata>> {
+        let version = client.runtime_version(Some(hash)).await?;
+        let mut versions = self.versions.lock().await;
+        if let Some(metadata) = versions.get(&version.spec_version) {
+            return Ok(metadata.clone());
+        }
+        let bytes = client.metadata(hash).await?;
+        let metadata = Arc::new(DecodedMetadata::decode(&bytes, version.spec_version)?);
+        info!(chain_id = %self.chain_id.as_str(), spec_version = version.spec_version, metadata_version = metadata.metadata_version, "Loaded runtime metadata");
+        self.service
+            .save_runtime_metadata(&self.chain_id, version.spec_version, bytes)
+            .await?;
+        versions.insert(version.spec_version, metadata.clone());
+        Ok(metadata)
+    }
+}
+
+// Fetch block `hash`, which must be at height `number`, and decode everything in it.
+async fn fetch_block(
+    client: &RpcClient,
+    metadata: &MetadataCache,
+    chain_id: &ID,
+    number: u64,
+    hash: &str,
+    finalized: bool,
+) -> Result<IndexedBlock> {
+    let block = client
+        .block(hash)
+        .await?
+        .ok_or_else(|| AppError::Rpc(format!("Node has no body for block {}", hash)))?;
+    let header = block.block.header;
+    if header.block_number()? != number {
+        return Err(AppError::Rpc(format!("Block {} does not have number {}", hash, number)));
+    }
+
+    let metadata = metadata.at(client, hash).await?;
+    let decoded = match client.storage(SYSTEM_EVENTS_KEY, hash).await? {
+        Some(bytes) => events::decode_events(&metadata, &bytes)?,
+        None => Vec::new(),
+    };
+    let decoded_extrinsics = extrinsics::decode_extrinsics(&metadata, &block.block.extrinsics)?;
+    let timestamp = match client.storage(TIMESTAMP_NOW_KEY, hash).await? {
+        Some(bytes) => decode_timestamp(&bytes)?,
+        None => Utc::now(),
+    };
+
+    let author = match digest::author_claim(&header.digest.logs) {
+        Some(claim) => block_author(client, hash, claim).await?,
+        None => None,
+    };
+
+    let extrinsics = decoded_extrinsics
+        .into_iter()
+        .map(|extrinsic| {
+            let (success, fee) = extrinsic_outcome(&decoded, extrinsic.index);
+            Extrinsic {
+                id: Extrinsic::id_for(number, extrinsic.index),
+                block_number: number,
+                index: extrinsic.index,
+                hash: extrinsic.hash,
+                signer: extrinsic.signer,
+                nonce: extrinsic.nonce,
+                tip: extrinsic.tip,
+                fee,
+                success,
+                pallet_name: extrinsic.pallet_name,
+                call_name: extrinsic.call_name,
+                args: extrinsic.args,
+                finalized,
+                chain_id: chain_id.clone(),
+            }
+        })
+        .collect();
+    let events = decoded
+        .into_iter()
+        .map(|event| Event {
+            // Deterministic so re-ingesting a block overwrites instead of duplicating
+            id: ID::from(format!("{}-{}-{}", chain_id.as_str(), number, event.index)),
+            block_number: number,
+            event_index: event.index,
+            extrinsic_id: event.extrinsic_index.map(|idx| Extrinsic::id_for(number, idx).to_string()),
+            timestamp,
+            pallet_name: event.pallet_name,
+            event_name: event.event_name,
+            data: event.data,
+            finalized,
+            chain_id: chain_id.clone(),
+        })
+        .collect();
+    Ok(IndexedBlock {
+        block: Block {
+            number,
+            hash: hash.to_string(),
+            parent_hash: header.parent_hash,
+            state_root: header.state_root,
+            extrinsics_root: header.extrinsics_root,
+            timestamp,
+            author,
+            finalized,
+            chain_id: chain_id.clone(),
+        },
+        extrinsics,
+        events,
+    })
+}
+
+// Whether the extrinsic at `index` succeeded and the fee it paid, read from the
+// `System.ExtrinsicSuccess`/`ExtrinsicFailed` and `TransactionPayment.TransactionFeePaid`
+// events it emitted. Unsigned extrinsics pay no fee.
+fn extrinsic_outcome(events: &[events::DecodedEvent], index: u32) -> (bool, Option<String>) {
+    let mut success = false;
+    let mut fee = None;
+    for event in events.iter().filter(|e| e.extrinsic_index == Some(index)) {
+        match (event.pallet_name.as_str(), event.event_name.as_str()) {
+            ("System", "ExtrinsicSuccess") => success = true,
+            ("TransactionPayment", "TransactionFeePaid") => {
+                fee = event.data.get("actual_fee").and_then(extrinsics::integer_to_string);
+            }
+            _ => {}
+        }
+    }
+    (success, fee)
+}
+
+// The validator the header's author claim points at, as a 0x-prefixed account id.
+async fn block_author(client: &RpcClient, hash: &str, claim: digest::AuthorClaim) -> Result<Option<String>> {
+    let Some(bytes) = client.storage(SESSION_VALIDATORS_KEY, hash).await? else {
+        return Ok(None);
+    };
+    let validators = Vec::<[u8; 32]>::decode(&mut &bytes[..])
+        .map_err(|e| AppError::Decode(format!("Invalid Session.Validators value: {}", e)))?;
+    Ok(claim.resolve(&validators).map(|account| format!("0x{}", hex::encode(account))))
+}
+
+// `system_properties` values are either scalars or arrays (one entry per token).
+fn first_property<'a>(properties: &'a Value, key: &str) -> Option<&'a Value> {
+    match properties.get(key)? {
+        Value::Array(values) => values.first(),
+        value => Some(value),
+    }
+}
+
+// `Timestamp.Now` is a SCALE u64 of milliseconds since the epoch.
+fn decode_timestamp(bytes: &[u8]) -> Result<DateTime<Utc>> {
+    let millis = u64::decode(&mut &bytes[..])
+        .map_err(|e| AppError::Decode(format!("Invalid Timestamp.Now value: {}", e)))?;
+    DateTime::from_timestamp_millis(millis as i64)
+        .ok_or_else(|| AppError::Decode(format!("Timestamp out of range: {}", millis)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, BackfillConfig};
+    use crate::indexer::ReplayFrom;
+    use crate::models::{BackfillState, BackfillStatus};
+    use crate::store::MemoryStore;
+    use tokio_stream::StreamExt;
+
+    const BLOCK_HASHES: [&str; 4] = [
+        "0xb2e9e199aa4d4b8ad82dd216bd489849a76f52184b586af0937772268fd53512",
+        "0x912d48aea3f1b47e3b5fc1a20a23866eeba3949317953366e696c521b93a4c2d",
+        "0x575817256f0950d8ddd9160da7f1240dce85defcc02e2f28e525ca75de699856",
+        "0x6f3be7561d65504d9145569890444d68dcfcc96c58636e4cf1c4b27fd2c6273b",
+    ];
+
+    // The recording's best heads are 1, 2, 3 and a block 4 on a sibling of that block 3,
+    // finalized heads 1 and 4.
+    async fn start_service() -> (SubstrateIndexerService, ID, String) {
+        let server = MockRpcServer::start(Recording::from_file("fixtures/rpc_recording.json").unwrap())
+            .await
+            .unwrap();
+        let chain = ChainConfig {
+            id: "dev".to_string(),
+            name: "Dev".to_string(),
+            endpoint: server.url(),
+            replay_file: None,
+            backfill_from_block: None,
+            ss58_prefix: 0,
+            token_symbol: "MDOT".to_string(),
+            decimals: 10,
+        };
+        let service = SubstrateIndexerService::new(AppConfig::for_chains(vec![chain]), Arc::new(MemoryStore::default()));
+        service.register_chains().await.unwrap();
+        (service, ID::from("dev"), server.url())
+    }
+
+    // Ingest until block 4 is finalized, then stop.
+    async fn ingest_recording(service: &SubstrateIndexerService, chain_id: &ID, endpoint: String) {
+        let (control, rx) = watch::channel(WorkerState::Running);
+        let handle = Ingestor::spawn(service.clone(), chain_id.clone(), endpoint, IngestionConfig::default(), rx);
+        let store = service.store();
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while store.finalized_block_height(chain_id).await.unwrap() != Some(4) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("block 4 was not finalized");
+        control.send(WorkerState::Stopped).unwrap();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn ingests_recorded_session() {
+        let (service, chain_id, endpoint) = start_service().await;
+        ingest_recording(&service, &chain_id, endpoint).await;
+        let store = service.store();
+
+        for (number, hash) in (1..=4).zip(BLOCK_HASHES) {
+            let block = store.get_block_by_number(&chain_id, number).await.unwrap().unwrap();
+            assert_eq!(block.hash, hash);
+            assert!(block.finalized);
+        }
+        assert!(store.get_block_by_number(&chain_id, 5).await.unwrap().is_none());
+
+        let events = store.list_events_in_blocks(&chain_id, &[1, 2, 3, 4]).await.unwrap();
+        let per_block: Vec<usize> = (1..=4)
+            .map(|number| events.iter().filter(|e| e.block_number == number).count())
+            .collect();
+        assert_eq!(per_block, [1, 5, 3, 5]);
+        assert!(events.iter().all(|e| e.finalized && e.chain_id == chain_id));
+        assert_eq!((events[0].pallet_name.as_str(), events[0].event_name.as_str()), ("System", "ExtrinsicSuccess"));
+
+        let chain_info = store.get_chain_info(&chain_id).await.unwrap().unwrap();
+        assert_eq!(chain_info.name, "Development");
+        assert_eq!(chain_info.version, "node-template-100");
+        assert_eq!(chain_info.token_symbol, "UNIT");
+        assert_eq!(chain_info.decimals, 12);
+        assert_eq!(chain_info.ssv58_prefix, 42);
+    }
+
+    // Block 3 is replaced when block 4 arrives on its sibling
+    #[tokio::test]
+    async fn orphaned_block_is_rolled_back() {
+        let (service, chain_id, endpoint) = start_service().await;
+        let mut events = service.watch_events(chain_id.clone(), None, ReplayFrom::Live).await.unwrap();
+        ingest_recording(&service, &chain_id, endpoint).await;
+
+        let mut orphaned_events = 0;
+        let reorg = loop {
+            let item = tokio::time::timeout(Duration::from_secs(5), events.next())
+                .await
+                .expect("no reorg within 5s")
+                .expect("stream ended");
+            match item {
+                Ok(event) if event.block_number == 3 => orphaned_events += 1,
+                Ok(_) => {}
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(orphaned_events, 7);
+        match reorg {
+            AppError::Reorg { from_block, orphaned_block_hashes } => {
+                assert_eq!(from_block, 3);
+                assert_eq!(
+                    orphaned_block_hashes,
+                    ["0x054812fd69c5cb4355ab34d10bf275c178ea04db30df71e1abf665cabeea1f01"]
+                );
+            }
+            other => panic!("expected a reorg, got {:?}", other),
+        }
+        // The replacing block's events follow
+        let next = events.next().await.unwrap().unwrap();
+        assert_eq!((next.block_number, next.event_index), (3, 0));
+
+        // Only the replacing block's events are left
+        let store = service.store();
+        let block_3 = store.list_events_in_blocks(&chain_id, &[3]).await.unwrap();
+        assert_eq!(block_3.len(), 3);
+        assert!(block_3.iter().all(|e| e.event_index < 3));
+
+        // Finalized blocks stay
+        assert!(service.rollback(&chain_id, 4).await.is_err());
+        assert!(service.rollback(&chain_id, 1).await.is_err());
+        let block_4 = store.get_block_by_number(&chain_id, 4).await.unwrap().unwrap();
+        assert_eq!(block_4.hash, BLOCK_HASHES[3]);
+        assert_eq!(store.list_events_in_blocks(&chain_id, &[4]).await.unwrap().len(), 5);
+    }
+
+    // The recording finalized up to block 4, block 5 could still be replaced
+    #[tokio::test]
+    async fn backfill_past_the_finalized_head_is_refused() {
+        let (service, chain_id, _) = start_service().await;
+        let backfiller = Backfiller::new(service.clone(), BackfillConfig::default(), Endpoints::default());
+
+        let err = backfiller.start(&chain_id, 1, Some(5)).await.unwrap_err();
+        assert!(matches!(err, AppError::InvalidInput(_)), "{}", err);
+        assert!(service.store().get_backfill(&chain_id).await.unwrap().is_none());
+
+        // The refused start released the chain, a backfill up to the finalized head runs
+        let status = backfiller.start(&chain_id, 1, None).await.unwrap();
+        assert_eq!(status.to_block, 4);
+        let status = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let status = service.store().get_backfill(&chain_id).await.unwrap().unwrap();
+                if status.state == BackfillState::Completed {
+                    break status;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("backfill not completed within 5s");
+        assert_eq!(status.next_block, 5);
+        let block_4 = service.store().get_block_by_number(&chain_id, 4).await.unwrap().unwrap();
+        assert_eq!(block_4.hash, BLOCK_HASHES[3]);
+    }
+
+    // A cancelled backfill is neither resumed nor started over on restart
+    #[tokio::test]
+    async fn cancelled_backfill_is_not_resumed() {
+        let (service, chain_id, _) = start_service().await;
+        let now = Utc::now();
+        let cancelled = BackfillStatus {
+            from_block: 1,
+            to_block: 4,
+            next_block: 2,
+            state: BackfillState::Cancelled,
+            error: None,
+            started_at: now,
+            updated_at: now,
+            chain_id: chain_id.clone(),
+        };
+        service.store().save_backfill(cancelled).await.unwrap();
+        let mut chains = service.chains();
+        chains[0].backfill_from_block = Some(1);
+
+        let backfiller = Backfiller::new(service.clone(), BackfillConfig::default(), Endpoints::default());
+        backfiller.start_configured(&chains).await;
+        let status = service.store().get_backfill(&chain_id).await.unwrap().unwrap();
+        assert_eq!((status.state, status.next_block), (BackfillState::Cancelled, 2));
+        // Nothing holds the chain, it can be backfilled again on request
+        backfiller.start(&chain_id, 1, None).await.unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+use crate::errors::{AppError, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, info, warn};
+
+// A recorded RPC session. `calls` answer plain requests, `notifications` are pushed
+// to the client, in order, as soon as it opens the named subscription.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Recording {
+    #[serde(default)]
+    pub calls: Vec<RecordedCall>,
+    #[serde(default)]
+    pub notifications: Vec<RecordedNotification>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecordedCall {
+    pub method: String,
+    // When absent the entry answers the method regardless of its params
+    #[serde(default)]
+    pub params: Option<Value>,
+    pub result: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecordedNotification {
+    // Subscribe method that opens the stream, e.g. `chain_subscribeFinalizedHeads`
+    pub subscription: String,
+    pub result: Value,
+}
+
+impl Recording {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| AppError::Internal(format!("Invalid RPC recording {}: {}", path.display(), e)))
+    }
+
+    fn find_call(&self, method: &str, params: &Value) -> Option<&RecordedCall> {
+        let params_match = |call: &&RecordedCall| call.params.as_ref() == Some(params);
+        let same_method = || self.calls.iter().filter(|call| call.method == method);
+        same_method()
+            .find(params_match)
+            .or_else(|| same_method().find(|call| call.params.is_none()))
+    }
+
+    fn is_subscription(&self, method: &str) -> bool {
+        self.notifications.iter().any(|n| n.subscription == method)
+    }
+}
+
+// Local WebSocket JSON-RPC server that replays a `Recording`, so ingestion can run
+// against deterministic chain data without a node.
+pub struct MockRpcServer {
+    addr: SocketAddr,
+}
+
+impl MockRpcServer {
+    // Bind to an ephemeral localhost port and serve every connection from `recording`.
+    pub async fn start(recording: Recording) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let recording = Arc::new(recording);
+        info!(%addr, calls = recording.calls.len(), notifications = recording.notifications.len(), "Mock RPC server listening");
+
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer)) => {
+                        debug!(%peer, "Mock RPC connection");
+                        tokio::spawn(serve(stream, recording.clone()));
+                    }
+                    Err(e) => {
+                        error!("Mock RPC accept failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Self { addr })
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+}
+
+async fn serve(stream: TcpStream, recording: Arc<Recording>) {
+    let mut socket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("Mock RPC handshake failed: {}", e);
+            return;
+        }
+    };
+    let mut next_subscription = 0u64;
+
+    while let Some(Ok(msg)) = socket.next().await {
+        let Message::Text(text) = msg else {
+            continue;
+        };
+        let Ok(request) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = request.get("params").cloned().unwrap_or_else(|| json!([]));
+
+        let mut replies = Vec::new();
+        if recording.is_subscription(method) {
+            next_subscription += 1;
+            let sub_id = format!("replay-{}", next_subscription);
+            replies.push(json!({ "jsonrpc": "2.0", "id": id, "result": sub_id }));
+            for notification in recording.notifications.iter().filter(|n| n.subscription == method) {
+                replies.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": { "subscription": sub_id, "result": notification.result },
+                }));
+            }
+        } else if let Some(call) = recording.find_call(method, &params) {
+            replies.push(json!({ "jsonrpc": "2.0", "id": id, "result": call.result }));
+        } else {
+            warn!(method, %params, "No recorded response");
+            replies.push(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("No recorded response for {}", method) },
+            }));
+        }
+
+        for reply in replies {
+            if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::errors::{AppError, Result};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use futures_util::{SinkExt, StreamExt};
+use parity_scale_codec::{Compact, Encode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, instrument, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct PendingRequest {
+    response: oneshot::Sender<Result<Value>>,
+    // Set for subscription requests: notifications are routed here once the node
+    // answers with the subscription id.
+    notifications: Option<mpsc::UnboundedSender<Value>>,
+}
+
+#[derive(Default)]
+struct Routing {
+    pending: HashMap<u64, PendingRequest>,
+    subscriptions: HashMap<String, mpsc::UnboundedSender<Value>>,
+}
+
+// Minimal JSON-RPC 2.0 client over a single WebSocket connection.
+// A background task owns the socket; dropping every clone of the client closes it.
+pub struct RpcClient {
+    outgoing: mpsc::UnboundedSender<Message>,
+    routing: Arc<Mutex<Routing>>,
+    next_id: AtomicU64,
+}
+
+// Stream of notifications for one subscription. Ends when the connection drops.
+pub struct Subscription {
+    pub id: String,
+    notifications: mpsc::UnboundedReceiver<Value>,
+}
+
+impl Subscription {
+    pub async fn next<T: DeserializeOwned>(&mut self) -> Option<Result<T>> {
+        let value = self.notifications.recv().await?;
+        Some(serde_json::from_value(value).map_err(|e| AppError::Rpc(format!("Malformed notification on {}: {}", self.id, e))))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHeader {
+    pub parent_hash: String,
+    pub number: String, // hex encoded
+    pub state_root: String,
+    pub extrinsics_root: String,
+    #[serde(default)]
+    pub digest: RpcDigest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RpcDigest {
+    pub logs: Vec<String>, // SCALE encoded `DigestItem`s, hex
+}
+
+impl RpcHeader {
+    pub fn block_number(&self) -> Result<u64> {
+        u64::from_str_radix(self.number.trim_start_matches("0x"), 16)
+            .map_err(|e| AppError::Rpc(format!("Invalid block number {}: {}", self.number, e)))
+    }
+
+    // Block hash: blake2b-256 of the SCALE encoded header. Block numbers are encoded compact,
+    // so the result does not depend on the chain's block number type.
+    pub fn hash(&self) -> Result<String> {
+        let mut encoded = decode_hex(&self.parent_hash)?;
+        Compact(self.block_number()?).encode_to(&mut encoded);
+        encoded.extend(decode_hex(&self.state_root)?);
+        encoded.extend(decode_hex(&self.extrinsics_root)?);
+        Compact(self.digest.logs.len() as u32).encode_to(&mut encoded);
+        for log in &self.digest.logs {
+            encoded.extend(decode_hex(log)?);
+        }
+        Ok(format!("0x{}", hex::encode(Blake2b::<U32>::digest(&encoded))))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcBlockBody {
+    pub header: RpcHeader,
+    pub extrinsics: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcSignedBlock {
+    pub block: RpcBlockBody,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcRuntimeVersion {
+    pub spec_name: String,
+    pub spec_version: u32,
+}
+
+impl RpcClient {
+    #[instrument]
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| AppError::Rpc(format!("Failed to connect to {}: {}", url, e)))?;
+        let (mut sink, mut stream) = socket.split();
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Message>();
+        let routing = Arc::new(Mutex::new(Routing::default()));
+
+        let task_routing = routing.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    msg = outgoing_rx.recv() => match msg {
+                        Some(msg) => {
+                            if let Err(e) = sink.send(msg).await {
+                                error!("RPC send failed: {}", e);
+                                break;
+                            }
+                        }
+                        // Every client handle is gone
+                        None => break,
+                    },
+                    msg = stream.next() => match msg {
+                        Some(Ok(Message::Text(text))) => dispatch(&task_routing, &text),
+                        Some(Ok(Message::Ping(payload))) => {
+                            let _ = sink.send(Message::Pong(payload)).await;
+                        }
+                        Some(Ok(Message::Close(frame))) => {
+                            warn!("RPC connection closed by server: {:?}", frame);
+                            break;
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            error!("RPC receive failed: {}", e);
+                            break;
+                        }
+                        None => break,
+                    },
+                }
+            }
+            // Fail everything still in flight and end all subscriptions
+            if let Ok(mut routing) = task_routing.lock() {
+                for (_, pending) in routing.pending.drain() {
+                    let _ = pending.response.send(Err(AppError::Rpc("Connection closed".to_string())));
+                }
+                routing.subscriptions.clear();
+            }
+            let _ = sink.close().await;
+        });
+
+        Ok(Self {
+            outgoing,
+            routing,
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    async fn call(&self, method: &str, params: Value, notifications: Option<mpsc::UnboundedSender<Value>>) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.routing
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock RPC routing table: {}", e)))?
+            .pending
+            .insert(id, PendingRequest { response: tx, notifications });
+
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        debug!(id, method, "RPC request");
+        self.outgoing
+            .send(Message::Text(request.to_string()))
+            .map_err(|_| AppError::Rpc("Connection closed".to_string()))?;
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(AppError::Rpc("Connection closed".to_string())),
+            Err(_) => {
+                if let Ok(mut routing) = self.routing.lock() {
+                    routing.pending.remove(&id);
+                }
+                Err(AppError::Rpc(format!("{} timed out after {:?}", method, REQUEST_TIMEOUT)))
+            }
+        }
+    }
+
+    pub async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let value = self.call(method, params, None).await?;
+        serde_json::from_value(value).map_err(|e| AppError::Rpc(format!("Malformed {} response: {}", method, e)))
+    }
+
+    pub async fn subscribe(&self, method: &str, params: Value) -> Result<Subscription> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let value = self.call(method, params, Some(tx)).await?;
+        let id = subscription_id(&value)
+            .ok_or_else(|| AppError::Rpc(format!("{} returned an invalid subscription id: {}", method, value)))?;
+        Ok(Subscription { id, notifications: rx })
+    }
+
+    // Heads of the node's best chain, which may switch branches before finality.
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription> {
+        self.subscribe("chain_subscribeNewHeads", json!([])).await
+    }
+
+    pub async fn subscribe_finalized_heads(&self) -> Result<Subscription> {
+        self.subscribe("chain_subscribeFinalizedHeads", json!([])).await
+    }
+
+    // Hash of the best chain's block at `number`, `None` past its head.
+    pub async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        self.request("chain_getBlockHash", json!([number])).await
+    }
+
+    pub async fn finalized_head(&self) -> Result<String> {
+        self.request("chain_getFinalizedHead", json!([])).await
+    }
+
+    pub async fn block(&self, hash: &str) -> Result<Option<RpcSignedBlock>> {
+        self.request("chain_getBlock", json!([hash])).await
+    }
+
+    // Raw SCALE bytes stored under `key` at block `at`, `None` if the key is empty.
+    pub async fn storage(&self, key: &str, at: &str) -> Result<Option<Vec<u8>>> {
+        let value: Option<String> = self.request("state_getStorage", json!([key, at])).await?;
+        value.map(|v| decode_hex(&v)).transpose()
+    }
+
+    pub async fn metadata(&self, at: &str) -> Result<Vec<u8>> {
+        let value: String = self.request("state_getMetadata", json!([at])).await?;
+        decode_hex(&value)
+    }
+
+    // Runtime version at block `at`, or at the best block when `None`.
+    pub async fn runtime_version(&self, at: Option<&str>) -> Result<RpcRuntimeVersion> {
+        self.request("state_getRuntimeVersion", json!([at])).await
+    }
+
+    pub async fn system_chain(&self) -> Result<String> {
+        self.request("system_chain", json!([])).await
+    }
+
+    pub async fn system_properties(&self) -> Result<Value> {
+        self.request("system_properties", json!([])).await
+    }
+}
+
+fn subscription_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+// Route an incoming frame to the waiting request or subscription.
+fn dispatch(routing: &Mutex<Routing>, text: &str) {
+    let message: Value = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("Ignoring malformed RPC frame: {}", e);
+            return;
+        }
+    };
+    let Ok(mut routing) = routing.lock() else {
+        return;
+    };
+
+    if let Some(id) = message.get("id").and_then(Value::as_u64) {
+        let Some(pending) = routing.pending.remove(&id) else {
+            return;
+        };
+        let result = match (message.get("result"), message.get("error")) {
+            (_, Some(err)) => Err(AppError::Rpc(format!("Node returned error: {}", err))),
+            (Some(result), None) => Ok(result.clone()),
+            (None, None) => Err(AppError::Rpc("Response without result".to_string())),
+        };
+        // Register before answering so no notification can slip through in between
+        if let (Ok(result), Some(notifications)) = (&result, pending.notifications) {
+            if let Some(sub_id) = subscription_id(result) {
+                routing.subscriptions.insert(sub_id, notifications);
+            }
+        }
+        let _ = pending.response.send(result);
+        return;
+    }
+
+    let params = message.get("params");
+    let sub_id = params.and_then(|p| p.get("subscription")).and_then(subscription_id);
+    if let (Some(sub_id), Some(result)) = (sub_id, params.and_then(|p| p.get("result"))) {
+        match routing.subscriptions.get(&sub_id) {
+            Some(tx) => {
+                if tx.send(result.clone()).is_err() {
+                    routing.subscriptions.remove(&sub_id);
+                }
+            }
+            None => debug!(%sub_id, "Notification for unknown subscription"),
+        }
+    }
+}
+
+pub fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|e| AppError::Rpc(format!("Invalid hex payload: {}", e)))
+}
@@ -1,21 +1,41 @@
 mod models;
+mod address;
+mod balance;
 mod indexer;
 mod schema;
 mod config;
 mod errors;
 mod dataloader;
+mod store;
+mod ingest;
+mod metadata;
+mod workers;
+mod auth;
+mod rate_limit;
+mod metrics;
+mod telemetry;
+mod health;
 
 use actix_web::{guard, web, App, HttpRequest, HttpResponse, HttpServer, middleware::Logger as ActixLogger};
 use async_graphql::http::{GraphQLPlaygroundConfig, playground_source};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use async_graphql::{EmptyMutation, Schema, extensions};
-use schema::{AppSchema, QueryRoot, SubscriptionRoot};
+use async_graphql::{Data, ErrorExtensions, Schema, extensions};
+use schema::{AppSchema, MutationRoot, QueryRoot, SubscriptionRoot};
 use indexer::SubstrateIndexerService;
-use dataloader::{AppDataloader, ChainInfoLoader};
-use crate::config::{CONFIG, ensure_config_files_exist, AppConfig};
+use dataloader::{AppDataloader, BlockEventsLoader, BlockLoader, ChainInfoLoader, ExtrinsicEventsLoader, ExtrinsicLoader};
+use async_graphql::dataloader::DataLoader;
+use crate::config::{CONFIG, ensure_config_files_exist};
 use crate::errors::AppError;
+use crate::auth::{connection_init_credential, request_credential, Authenticator};
+use crate::workers::Workers;
+use crate::rate_limit::{ClientKey, RateLimit, RateLimiter};
+use crate::metrics::{GraphQLMetrics, METRICS};
+use crate::health::HealthChecker;
+use async_graphql::ID;
 
 use tracing_subscriber::fmt::format::FmtSpan;
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 async fn gql_playground() -> Result<HttpResponse, AppError> {
@@ -26,21 +46,82 @@ async fn gql_playground() -> Result<HttpResponse, AppError> {
         )))
 }
 
-async fn gql_request(schema: web::Data<AppSchema>, req: GraphQLRequest) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+// Prometheus scrape target, the store gauges are read at scrape time
+async fn metrics(indexer_service: web::Data<SubstrateIndexerService>) -> Result<HttpResponse, AppError> {
+    METRICS.refresh_store(&indexer_service, &CONFIG.storage).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(METRICS.render()?))
+}
+
+// Kubernetes probes, 503 while not live or not ready. Both answer with the full report.
+async fn healthz(health: web::Data<HealthChecker>) -> HttpResponse {
+    let status = health.check().await;
+    probe_response(status.live).json(status)
+}
+
+async fn readyz(health: web::Data<HealthChecker>) -> HttpResponse {
+    let status = health.check().await;
+    probe_response(status.ready).json(status)
+}
+
+fn probe_response(ok: bool) -> actix_web::HttpResponseBuilder {
+    if ok {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    }
+}
+
+// Requests with a bad credential are refused before anything is resolved, the rest are
+// rate limited by the schema. Traced as part of the caller's trace, if it sent one.
+async fn gql_request(
+    schema: web::Data<AppSchema>,
+    authenticator: web::Data<Authenticator>,
+    http_req: HttpRequest,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    let credential = request_credential(&http_req);
+    async {
+        match authenticator.authenticate(credential.as_deref()).await {
+            Ok(caller) => {
+                let client = ClientKey::new(&caller, http_req.peer_addr());
+                schema.execute(req.into_inner().data(caller).data(client)).await.into()
+            }
+            Err(e) => {
+                METRICS.observe_refused();
+                async_graphql::Response::from_errors(vec![e.to_server_error()]).into()
+            }
+        }
+    }
+    .instrument(telemetry::request_span(&http_req))
+    .await
 }
 
+// Subscriptions authenticate with their `connection_init` payload, falling back to the
+// upgrade request's headers. A bad credential closes the connection.
 async fn gql_ws(
     schema: web::Data<AppSchema>,
+    authenticator: web::Data<Authenticator>,
     http_req: HttpRequest,
     payload: web::Payload,
 ) -> Result<HttpResponse, actix_web::Error> { // actix_web::Error is compatible with AppError via From trait if needed or map directly
+    let authenticator = Authenticator::clone(&authenticator);
+    let header_credential = request_credential(&http_req);
+    let peer_addr = http_req.peer_addr();
     GraphQLSubscription::new(Schema::clone(&*schema))
+        .on_connection_init(move |init_payload| async move {
+            let credential = connection_init_credential(&init_payload).or(header_credential);
+            let caller = authenticator.authenticate(credential.as_deref()).await.map_err(|e| e.extend())?;
+            let mut data = Data::default();
+            data.insert(ClientKey::new(&caller, peer_addr));
+            data.insert(caller);
+            Ok(data)
+        })
         .start(&http_req, payload)
-        .await
 }
 
-fn init_tracer() {
+fn init_tracer() -> Result<(), AppError> {
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(CONFIG.logger.level.clone()));
 
@@ -51,36 +132,72 @@ fn init_tracer() {
         .with_file(true)
         .with_line_number(true)
         .finish();
-    
+    // Spans also go to the OTLP collector when one is configured
+    let subscriber = subscriber.with(telemetry::layer(&CONFIG.telemetry)?);
+
     tracing::subscriber::set_global_default(subscriber)
         .expect("Failed to set global default tracing subscriber");
+    Ok(())
 }
 
 #[actix_web::main]
 async fn main() -> Result<(), AppError> {
     dotenvy::dotenv().ok();
     ensure_config_files_exist()?;
-    init_tracer();
+    init_tracer()?;
 
     let app_config = CONFIG.clone(); // Clone once for multiple uses
     tracing::info!("Starting service with config: {:?}", app_config);
 
-    let indexer_service = SubstrateIndexerService::new(app_config.clone());
-    
-    // Start the mock event generator with its own config clone
-    SubstrateIndexerService::simulate_new_event(app_config.clone());
+    let chain_ids: Vec<ID> = app_config.chains.iter().map(|c| ID::from(c.id.as_str())).collect();
+    let store = store::from_config(&app_config.storage, &chain_ids)?;
+    let authenticator = Authenticator::new(store.clone(), &app_config.auth)?;
+    authenticator.log_state().await?;
+    let indexer_service = SubstrateIndexerService::new(app_config.clone(), store);
+    indexer_service.register_chains().await?;
+
+    // Real chain data from the Substrate nodes (or recorded sessions) when ingestion is
+    // enabled, mock data otherwise. One worker per chain, admins can switch them later.
+    let endpoints = ingest::Endpoints::default();
+    let workers = Workers::new(indexer_service.clone(), app_config.clone(), endpoints.clone());
+    for chain_id in indexer_service.chain_ids() {
+        workers.start(&chain_id, workers.default_kind()).await?;
+    }
+    // History is backfilled alongside, from the same endpoints
+    let backfiller = ingest::Backfiller::new(indexer_service.clone(), app_config.backfill.clone(), endpoints);
+    if app_config.ingestion.enabled {
+        backfiller.start_configured(&indexer_service.chains()).await;
+    }
 
     // Create Dataloader
     let chain_info_loader = ChainInfoLoader::new(indexer_service.clone());
     let dataloader = AppDataloader::new(chain_info_loader, tokio::spawn);
+    let block_loader = DataLoader::new(BlockLoader::new(indexer_service.clone()), tokio::spawn);
+    let block_events_loader = DataLoader::new(BlockEventsLoader::new(indexer_service.clone()), tokio::spawn);
+    let extrinsic_loader = DataLoader::new(ExtrinsicLoader::new(indexer_service.clone()), tokio::spawn);
+    let extrinsic_events_loader = DataLoader::new(ExtrinsicEventsLoader::new(indexer_service.clone()), tokio::spawn);
 
-    let schema = Schema::build(QueryRoot, EmptyMutation, SubscriptionRoot)
+    let metrics_service = indexer_service.clone();
+    let health = HealthChecker::new(indexer_service.clone(), workers.clone(), &app_config.ingestion);
+    let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
         .data(indexer_service)      // Indexer service for direct calls
+        .data(workers)
+        .data(health.clone())
+        .data(backfiller)
+        .data(authenticator.clone())
         .data(dataloader)           // Dataloader for batched calls
+        .data(block_loader)
+        .data(block_events_loader)
+        .data(extrinsic_loader)
+        .data(extrinsic_events_loader)
+        .limit_depth(app_config.limits.max_depth)
+        .limit_complexity(app_config.limits.max_complexity)
+        .extension(GraphQLMetrics)
+        .extension(RateLimit(RateLimiter::new(&app_config.limits)))
         .data(app_config)           // App config if needed directly in resolvers
         .extension(extensions::Logger)      // Built-in logger
         .extension(extensions::Tracing)     // Tracing integration
-        .extension(extensions::Analyzer)    // Query analyzer (helps prevent overly complex queries)
+        .extension(extensions::Analyzer)    // Reports each query's depth and complexity
       //.extension(extensions::ApolloTracing) // If you need Apollo Tracing format
         .finish();
 
@@ -88,13 +205,21 @@ async fn main() -> Result<(), AppError> {
     tracing::info!("Playground: http://{}/", server_addr);
     tracing::info!("GraphQL endpoint: http://{}/graphql", server_addr);
     tracing::info!("GraphQL subscription WebSocket: ws://{}/ws", server_addr);
+    tracing::info!("Prometheus metrics: http://{}/metrics", server_addr);
+    tracing::info!("Probes: http://{}/healthz and http://{}/readyz", server_addr, server_addr);
 
     HttpServer::new(move || {
         App::new()
             .wrap(ActixLogger::default())
             .app_data(web::Data::new(schema.clone()))
+            .app_data(web::Data::new(authenticator.clone()))
+            .app_data(web::Data::new(metrics_service.clone()))
+            .app_data(web::Data::new(health.clone()))
             .service(web::resource("/").guard(guard::Get()).to(gql_playground))
             .service(web::resource("/graphql").guard(guard::Post()).to(gql_request))
+            .service(web::resource("/metrics").guard(guard::Get()).to(metrics))
+            .service(web::resource("/healthz").guard(guard::Get()).to(healthz))
+            .service(web::resource("/readyz").guard(guard::Get()).to(readyz))
             .service(
                 web::resource("/ws")
                     .guard(guard::Get())
@@ -104,6 +229,8 @@ async fn main() -> Result<(), AppError> {
     })
     .bind(server_addr)?
     .run()
-    .await
-    .map_err(AppError::Io)
+    .await?;
+
+    telemetry::shutdown().await;
+    Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,390 @@
+use crate::errors::{AppError, Result};
+use crate::models::{
+    CallMetadata, ConstantMetadata, ErrorMetadata, EventMetadata, FieldMetadata, PalletMetadata, RuntimeMetadata,
+    StorageItemMetadata,
+};
+use frame_metadata::v14::{
+    ExtrinsicMetadata, PalletConstantMetadata, PalletStorageMetadata, StorageEntryModifier, StorageEntryType,
+};
+use frame_metadata::{RuntimeMetadata as FrameMetadata, RuntimeMetadataPrefixed};
+use parity_scale_codec::Decode;
+use scale_info::form::PortableForm;
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use scale_value::{Composite, Primitive, Value, ValueDef};
+use serde_json::{json, Value as JsonValue};
+
+// Type names nest through generics; stop rendering past this depth.
+const MAX_TYPE_NAME_DEPTH: usize = 8;
+
+// Pallet description shared by V14 and V15, which only differ in pallet docs.
+struct Pallet {
+    name: String,
+    index: u8,
+    docs: Vec<String>,
+    storage: Option<PalletStorageMetadata<PortableForm>>,
+    calls: Option<u32>,
+    event: Option<u32>,
+    error: Option<u32>,
+    constants: Vec<PalletConstantMetadata<PortableForm>>,
+}
+
+// Type ids needed to decode an extrinsic, V14 only carries them as type parameters of
+// `UncheckedExtrinsic<Address, Call, Signature, Extra>`.
+pub struct ExtrinsicTypes {
+    pub version: u8,
+    pub address: u32,
+    pub call: u32,
+    pub signature: u32,
+    // (identifier, type id) of each signed extension, in encoding order
+    pub signed_extensions: Vec<(String, u32)>,
+}
+
+// Runtime metadata as returned by `state_getMetadata`, normalised across V14 and V15.
+pub struct DecodedMetadata {
+    pub spec_version: u32,
+    pub metadata_version: u32,
+    pub types: PortableRegistry,
+    // Type id of `System.Events`, i.e. `Vec<EventRecord<RuntimeEvent, Hash>>`
+    pub events_type: u32,
+    // `None` when a V14 runtime does not expose the extrinsic's type parameters
+    pub extrinsic: Option<ExtrinsicTypes>,
+    pallets: Vec<Pallet>,
+}
+
+impl DecodedMetadata {
+    pub fn decode(bytes: &[u8], spec_version: u32) -> Result<Self> {
+        let prefixed = RuntimeMetadataPrefixed::decode(&mut &bytes[..])
+            .map_err(|e| AppError::Decode(format!("Invalid runtime metadata: {}", e)))?;
+        let metadata_version = prefixed.1.version();
+
+        let (types, pallets, extrinsic): (PortableRegistry, Vec<Pallet>, Option<ExtrinsicTypes>) = match prefixed.1 {
+            FrameMetadata::V14(m) => {
+                let pallets = m
+                    .pallets
+                    .into_iter()
+                    .map(|p| Pallet {
+                        name: p.name,
+                        index: p.index,
+                        docs: Vec::new(),
+                        storage: p.storage,
+                        calls: p.calls.map(|c| c.ty.id),
+                        event: p.event.map(|e| e.ty.id),
+                        error: p.error.map(|e| e.ty.id),
+                        constants: p.constants,
+                    })
+                    .collect();
+                let extrinsic = v14_extrinsic_types(&m.types, &m.extrinsic);
+                (m.types, pallets, extrinsic)
+            }
+            FrameMetadata::V15(m) => {
+                let pallets = m
+                    .pallets
+                    .into_iter()
+                    .map(|p| Pallet {
+                        name: p.name,
+                        index: p.index,
+                        docs: p.docs,
+                        storage: p.storage,
+                        calls: p.calls.map(|c| c.ty.id),
+                        event: p.event.map(|e| e.ty.id),
+                        error: p.error.map(|e| e.ty.id),
+                        constants: p.constants,
+                    })
+                    .collect();
+                let extrinsic = ExtrinsicTypes {
+                    version: m.extrinsic.version,
+                    address: m.extrinsic.address_ty.id,
+                    call: m.extrinsic.call_ty.id,
+                    signature: m.extrinsic.signature_ty.id,
+                    signed_extensions: m
+                        .extrinsic
+                        .signed_extensions
+                        .iter()
+                        .map(|e| (e.identifier.clone(), e.ty.id))
+                        .collect(),
+                };
+                (m.types, pallets, Some(extrinsic))
+            }
+            _ => {
+                return Err(AppError::Decode(format!(
+                    "Unsupported metadata version V{}, only V14 and V15 are supported",
+                    metadata_version
+                )))
+            }
+        };
+
+        let events_type = pallets
+            .iter()
+            .find(|p| p.name == "System")
+            .and_then(|p| plain_storage_type(p.storage.as_ref(), "Events"))
+            .ok_or_else(|| AppError::Decode("Metadata has no System.Events storage entry".to_string()))?;
+
+        Ok(Self {
+            spec_version,
+            metadata_version,
+            types,
+            events_type,
+            extrinsic,
+            pallets,
+        })
+    }
+
+    // Build the GraphQL view of the metadata. Done once per runtime version and cached.
+    pub fn to_graphql(&self) -> RuntimeMetadata {
+        let mut pallets: Vec<PalletMetadata> = self.pallets.iter().map(|p| self.pallet_to_graphql(p)).collect();
+        pallets.sort_by_key(|p| p.index);
+        RuntimeMetadata {
+            spec_version: self.spec_version,
+            metadata_version: self.metadata_version,
+            pallets,
+        }
+    }
+
+    fn pallet_to_graphql(&self, pallet: &Pallet) -> PalletMetadata {
+        let variants = |ty: Option<u32>| -> Vec<&scale_info::Variant<PortableForm>> {
+            match ty.and_then(|id| self.types.resolve(id)).map(|t| &t.type_def) {
+                Some(TypeDef::Variant(v)) => v.variants.iter().collect(),
+                _ => Vec::new(),
+            }
+        };
+
+        PalletMetadata {
+            name: pallet.name.clone(),
+            index: pallet.index,
+            docs: pallet.docs.clone(),
+            calls: variants(pallet.calls)
+                .into_iter()
+                .map(|v| CallMetadata {
+                    name: v.name.clone(),
+                    index: v.index,
+                    fields: self.fields_to_graphql(&v.fields),
+                    docs: v.docs.clone(),
+                })
+                .collect(),
+            events: variants(pallet.event)
+                .into_iter()
+                .map(|v| EventMetadata {
+                    name: v.name.clone(),
+                    index: v.index,
+                    fields: self.fields_to_graphql(&v.fields),
+                    docs: v.docs.clone(),
+                })
+                .collect(),
+            errors: variants(pallet.error)
+                .into_iter()
+                .map(|v| ErrorMetadata {
+                    name: v.name.clone(),
+                    index: v.index,
+                    docs: v.docs.clone(),
+                })
+                .collect(),
+            storage: pallet
+                .storage
+                .iter()
+                .flat_map(|s| s.entries.iter().map(move |e| (s.prefix.clone(), e)))
+                .map(|(prefix, entry)| {
+                    let (hashers, key_type, value_type) = match &entry.ty {
+                        StorageEntryType::Plain(ty) => (Vec::new(), None, self.type_name(ty.id)),
+                        StorageEntryType::Map { hashers, key, value } => (
+                            hashers.iter().map(|h| format!("{:?}", h)).collect(),
+                            Some(self.type_name(key.id)),
+                            self.type_name(value.id),
+                        ),
+                    };
+                    StorageItemMetadata {
+                        name: entry.name.clone(),
+                        prefix,
+                        modifier: match entry.modifier {
+                            StorageEntryModifier::Optional => "Optional".to_string(),
+                            StorageEntryModifier::Default => "Default".to_string(),
+                        },
+                        hashers,
+                        key_type,
+                        value_type,
+                        docs: entry.docs.clone(),
+                    }
+                })
+                .collect(),
+            constants: pallet
+                .constants
+                .iter()
+                .map(|c| ConstantMetadata {
+                    name: c.name.clone(),
+                    resolved_type: self.type_name(c.ty.id),
+                    value: format!("0x{}", hex::encode(&c.value)),
+                    decoded: scale_value::scale::decode_as_type(&mut &c.value[..], c.ty.id, &self.types)
+                        .map(|v| value_to_json(&self.types, &v))
+                        .unwrap_or(JsonValue::Null),
+                    docs: c.docs.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn fields_to_graphql(&self, fields: &[scale_info::Field<PortableForm>]) -> Vec<FieldMetadata> {
+        fields
+            .iter()
+            .map(|f| FieldMetadata {
+                name: f.name.clone(),
+                type_name: f.type_name.clone(),
+                type_id: f.ty.id,
+                resolved_type: self.type_name(f.ty.id),
+                docs: f.docs.clone(),
+            })
+            .collect()
+    }
+
+    // Human readable rendering of a registry type, e.g. `Vec<AccountId32>` or `[u8; 32]`.
+    pub fn type_name(&self, id: u32) -> String {
+        type_name(&self.types, id, 0)
+    }
+}
+
+fn type_name(types: &PortableRegistry, id: u32, depth: usize) -> String {
+    let Some(ty) = types.resolve(id) else {
+        return format!("<unknown type {}>", id);
+    };
+    if depth > MAX_TYPE_NAME_DEPTH {
+        return ty.path.segments.last().cloned().unwrap_or_else(|| "..".to_string());
+    }
+    let nested = |id: u32| type_name(types, id, depth + 1);
+    match &ty.type_def {
+        TypeDef::Primitive(p) => primitive_name(p).to_string(),
+        TypeDef::Sequence(seq) => format!("Vec<{}>", nested(seq.type_param.id)),
+        TypeDef::Array(arr) => format!("[{}; {}]", nested(arr.type_param.id), arr.len),
+        TypeDef::Tuple(tuple) => format!("({})", tuple.fields.iter().map(|f| nested(f.id)).collect::<Vec<_>>().join(", ")),
+        TypeDef::Compact(compact) => format!("Compact<{}>", nested(compact.type_param.id)),
+        TypeDef::BitSequence(_) => "BitVec".to_string(),
+        TypeDef::Composite(_) | TypeDef::Variant(_) => {
+            let ident = ty.path.segments.last().cloned().unwrap_or_else(|| format!("<anonymous type {}>", id));
+            let params: Vec<String> = ty.type_params.iter().filter_map(|p| p.ty.map(|t| nested(t.id))).collect();
+            if params.is_empty() {
+                ident
+            } else {
+                format!("{}<{}>", ident, params.join(", "))
+            }
+        }
+    }
+}
+
+fn primitive_name(primitive: &TypeDefPrimitive) -> &'static str {
+    match primitive {
+        TypeDefPrimitive::Bool => "bool",
+        TypeDefPrimitive::Char => "char",
+        TypeDefPrimitive::Str => "str",
+        TypeDefPrimitive::U8 => "u8",
+        TypeDefPrimitive::U16 => "u16",
+        TypeDefPrimitive::U32 => "u32",
+        TypeDefPrimitive::U64 => "u64",
+        TypeDefPrimitive::U128 => "u128",
+        TypeDefPrimitive::U256 => "u256",
+        TypeDefPrimitive::I8 => "i8",
+        TypeDefPrimitive::I16 => "i16",
+        TypeDefPrimitive::I32 => "i32",
+        TypeDefPrimitive::I64 => "i64",
+        TypeDefPrimitive::I128 => "i128",
+        TypeDefPrimitive::I256 => "i256",
+    }
+}
+
+fn v14_extrinsic_types(types: &PortableRegistry, extrinsic: &ExtrinsicMetadata<PortableForm>) -> Option<ExtrinsicTypes> {
+    let params = &types.resolve(extrinsic.ty.id)?.type_params;
+    let param = |name: &str| params.iter().find(|p| p.name == name).and_then(|p| p.ty).map(|t| t.id);
+    Some(ExtrinsicTypes {
+        version: extrinsic.version,
+        address: param("Address")?,
+        call: param("Call")?,
+        signature: param("Signature")?,
+        signed_extensions: extrinsic
+            .signed_extensions
+            .iter()
+            .map(|e| (e.identifier.clone(), e.ty.id))
+            .collect(),
+    })
+}
+
+fn plain_storage_type(storage: Option<&PalletStorageMetadata<PortableForm>>, name: &str) -> Option<u32> {
+    storage?
+        .entries
+        .iter()
+        .find(|entry| entry.name == name)
+        .and_then(|entry| match &entry.ty {
+            StorageEntryType::Plain(ty) => Some(ty.id),
+            StorageEntryType::Map { .. } => None,
+        })
+}
+
+// Convert a decoded SCALE value into JSON that reads naturally in GraphQL responses:
+// byte arrays become 0x-hex, newtypes are unwrapped, fieldless enum variants become strings
+// and integers that don't fit a JSON number are emitted as decimal strings.
+pub fn value_to_json(types: &PortableRegistry, value: &Value<u32>) -> JsonValue {
+    match &value.value {
+        ValueDef::Primitive(p) => primitive_to_json(p),
+        ValueDef::BitSequence(bits) => JsonValue::Array(bits.iter().map(JsonValue::Bool).collect()),
+        ValueDef::Variant(v) => match (v.name.as_str(), &v.values) {
+            ("None", c) if c.is_empty() => JsonValue::Null,
+            ("Some", Composite::Unnamed(vals)) if vals.len() == 1 => value_to_json(types, &vals[0]),
+            (name, c) if c.is_empty() => JsonValue::String(name.to_string()),
+            (name, c) => json!({ name: composite_to_json(types, c) }),
+        },
+        ValueDef::Composite(c) => {
+            if is_byte_sequence(types, value.context) {
+                let bytes: Vec<u8> = c
+                    .values()
+                    .filter_map(|v| match v.value {
+                        ValueDef::Primitive(Primitive::U128(b)) => u8::try_from(b).ok(),
+                        _ => None,
+                    })
+                    .collect();
+                return JsonValue::String(format!("0x{}", hex::encode(bytes)));
+            }
+            composite_to_json(types, c)
+        }
+    }
+}
+
+fn composite_to_json(types: &PortableRegistry, composite: &Composite<u32>) -> JsonValue {
+    match composite {
+        Composite::Named(fields) => JsonValue::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value_to_json(types, value)))
+                .collect(),
+        ),
+        // Newtype wrappers such as AccountId32([u8; 32])
+        Composite::Unnamed(values) if values.len() == 1 => value_to_json(types, &values[0]),
+        Composite::Unnamed(values) => JsonValue::Array(values.iter().map(|v| value_to_json(types, v)).collect()),
+    }
+}
+
+// Largest integer a JSON client reading numbers as doubles gets back exactly (2^53 - 1).
+// Anything past it is a decimal string instead.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+fn primitive_to_json(primitive: &Primitive) -> JsonValue {
+    match primitive {
+        Primitive::Bool(b) => JsonValue::Bool(*b),
+        Primitive::Char(c) => JsonValue::String(c.to_string()),
+        Primitive::String(s) => JsonValue::String(s.clone()),
+        Primitive::U128(n) if *n <= MAX_SAFE_INTEGER as u128 => json!(*n as u64),
+        Primitive::I128(n) if n.unsigned_abs() <= MAX_SAFE_INTEGER as u128 => json!(*n as i64),
+        Primitive::U128(n) => JsonValue::String(n.to_string()),
+        Primitive::I128(n) => JsonValue::String(n.to_string()),
+        Primitive::U256(bytes) | Primitive::I256(bytes) => JsonValue::String(format!("0x{}", hex::encode(bytes))),
+    }
+}
+
+fn is_byte_sequence(types: &PortableRegistry, type_id: u32) -> bool {
+    let Some(ty) = types.resolve(type_id) else {
+        return false;
+    };
+    let element = match &ty.type_def {
+        TypeDef::Sequence(seq) => seq.type_param.id,
+        TypeDef::Array(arr) => arr.type_param.id,
+        _ => return false,
+    };
+    matches!(
+        types.resolve(element).map(|t| &t.type_def),
+        Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+    )
+}
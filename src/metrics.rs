@@ -0,0 +1,252 @@
+use crate::config::{StorageBackend, StorageConfig};
+use crate::errors::{AppError, Result};
+use crate::indexer::SubstrateIndexerService;
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextPrepareRequest, NextRequest, NextResolve, NextSubscribe, ResolveInfo,
+};
+use async_graphql::parser::types::{ExecutableDocument, OperationType};
+use async_graphql::{Request, Response, ServerResult, Value, Variables};
+use futures_util::stream::{BoxStream, StreamExt};
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// Everything exported on /metrics
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    // By operation type, "unknown" for requests refused before their document was parsed.
+    // Not by operation name: clients pick those, every new one would add series.
+    pub requests: IntCounterVec,
+    pub request_duration: HistogramVec,
+    // By `Type.field`
+    pub resolver_duration: HistogramVec,
+    pub dataloader_batch_size: HistogramVec,
+    pub active_subscriptions: IntGauge,
+    // Events subscribers missed on the broadcast channel and got replayed from the store
+    pub lagged_events: IntCounterVec,
+    // The node's best head as ingestion last saw it
+    pub chain_head: IntGaugeVec,
+    // Refreshed from the store on every scrape, see `refresh_store`
+    pub indexed_height: IntGaugeVec,
+    pub finalized_height: IntGaugeVec,
+    pub store_events: IntGaugeVec,
+    pub store_size_bytes: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let requests = IntCounterVec::new(
+            Opts::new("graphql_requests_total", "GraphQL requests by operation type and outcome"),
+            &["operation_type", "outcome"],
+        )
+        .unwrap();
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new("graphql_request_duration_seconds", "GraphQL request latency by operation type"),
+            &["operation_type"],
+        )
+        .unwrap();
+        let resolver_duration = HistogramVec::new(
+            HistogramOpts::new("graphql_resolver_duration_seconds", "Field resolver latency")
+                .buckets(vec![0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0]),
+            &["field"],
+        )
+        .unwrap();
+        let dataloader_batch_size = HistogramVec::new(
+            HistogramOpts::new("graphql_dataloader_batch_size", "Keys loaded per dataloader batch")
+                .buckets(vec![1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0]),
+            &["loader"],
+        )
+        .unwrap();
+        let active_subscriptions = IntGauge::new("graphql_active_subscriptions", "Open GraphQL subscriptions").unwrap();
+        let lagged_events = IntCounterVec::new(
+            Opts::new("subscription_lagged_events_total", "Events subscribers fell behind on, by chain"),
+            &["chain"],
+        )
+        .unwrap();
+        let chain_head = IntGaugeVec::new(Opts::new("chain_head_height", "Best block reported by the node"), &["chain"]).unwrap();
+        let indexed_height = IntGaugeVec::new(Opts::new("chain_indexed_height", "Highest block in the store"), &["chain"]).unwrap();
+        let finalized_height =
+            IntGaugeVec::new(Opts::new("chain_finalized_height", "Highest finalized block in the store"), &["chain"]).unwrap();
+        let store_events = IntGaugeVec::new(Opts::new("store_events", "Events in the store, by chain"), &["chain"]).unwrap();
+        let store_size_bytes = IntGauge::new("store_size_bytes", "Size of the SQLite database and its write-ahead log").unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry.register(Box::new(request_duration.clone())).unwrap();
+        registry.register(Box::new(resolver_duration.clone())).unwrap();
+        registry.register(Box::new(dataloader_batch_size.clone())).unwrap();
+        registry.register(Box::new(active_subscriptions.clone())).unwrap();
+        registry.register(Box::new(lagged_events.clone())).unwrap();
+        registry.register(Box::new(chain_head.clone())).unwrap();
+        registry.register(Box::new(indexed_height.clone())).unwrap();
+        registry.register(Box::new(finalized_height.clone())).unwrap();
+        registry.register(Box::new(store_events.clone())).unwrap();
+        registry.register(Box::new(store_size_bytes.clone())).unwrap();
+
+        Self {
+            registry,
+            requests,
+            request_duration,
+            resolver_duration,
+            dataloader_batch_size,
+            active_subscriptions,
+            lagged_events,
+            chain_head,
+            indexed_height,
+            finalized_height,
+            store_events,
+            store_size_bytes,
+        }
+    }
+
+    // A request answered with an error before it reached the schema, e.g. for a bad credential
+    pub fn observe_refused(&self) {
+        self.requests.with_label_values(&["unknown", "error"]).inc();
+    }
+
+    pub fn observe_batch(&self, loader: &str, keys: usize) {
+        self.dataloader_batch_size.with_label_values(&[loader]).observe(keys as f64);
+    }
+
+    // Update the gauges read from the store, for every chain in the registry
+    pub async fn refresh_store(&self, service: &SubstrateIndexerService, storage: &StorageConfig) -> Result<()> {
+        let store = service.store();
+        for chain_id in service.chain_ids() {
+            let chain = chain_id.as_str();
+            let indexed = store.indexed_block_height(&chain_id).await?.unwrap_or(0);
+            let finalized = store.finalized_block_height(&chain_id).await?.unwrap_or(0);
+            let events = store.count_events(&chain_id, None).await?;
+            self.indexed_height.with_label_values(&[chain]).set(indexed as i64);
+            self.finalized_height.with_label_values(&[chain]).set(finalized as i64);
+            self.store_events.with_label_values(&[chain]).set(events as i64);
+        }
+        if let StorageBackend::Sqlite = storage.backend {
+            let mut size = tokio::fs::metadata(&storage.sqlite_path).await?.len();
+            // Recent writes may still sit in the write-ahead log
+            if let Ok(wal) = tokio::fs::metadata(format!("{}-wal", storage.sqlite_path)).await {
+                size += wal.len();
+            }
+            self.store_size_bytes.set(size as i64);
+        }
+        Ok(())
+    }
+
+    // Prometheus text exposition format
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| AppError::Internal(format!("Failed to encode metrics: {}", e)))?;
+        String::from_utf8(buffer).map_err(|e| AppError::Internal(format!("Metrics are not UTF-8: {}", e)))
+    }
+}
+
+// Schema extension recording request counts and latency, resolver timings and open
+// subscriptions.
+pub struct GraphQLMetrics;
+
+impl ExtensionFactory for GraphQLMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLMetricsExtension::default())
+    }
+}
+
+// Created per request
+#[derive(Default)]
+struct GraphQLMetricsExtension {
+    // The operation the request asked for, then the type of the one it runs
+    operation_name: Mutex<Option<String>>,
+    operation_type: Mutex<Option<&'static str>>,
+}
+
+// Decrements the gauge when the subscription's stream is dropped
+struct ActiveSubscription;
+
+impl ActiveSubscription {
+    fn open() -> Self {
+        METRICS.active_subscriptions.inc();
+        Self
+    }
+}
+
+impl Drop for ActiveSubscription {
+    fn drop(&mut self) {
+        METRICS.active_subscriptions.dec();
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for GraphQLMetricsExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let started = Instant::now();
+        let response = next.run(ctx).await;
+        let operation_type = self.operation_type.lock().ok().and_then(|ty| *ty).unwrap_or("unknown");
+        let outcome = if response.is_ok() { "ok" } else { "error" };
+        METRICS.requests.with_label_values(&[operation_type, outcome]).inc();
+        METRICS
+            .request_duration
+            .with_label_values(&[operation_type])
+            .observe(started.elapsed().as_secs_f64());
+        response
+    }
+
+    fn subscribe<'s>(&self, ctx: &ExtensionContext<'_>, stream: BoxStream<'s, Response>, next: NextSubscribe<'_>) -> BoxStream<'s, Response> {
+        let active = ActiveSubscription::open();
+        next.run(ctx, stream)
+            .map(move |response| {
+                let _active = &active;
+                response
+            })
+            .boxed()
+    }
+
+    async fn prepare_request(&self, ctx: &ExtensionContext<'_>, request: Request, next: NextPrepareRequest<'_>) -> ServerResult<Request> {
+        if let Ok(mut operation_name) = self.operation_name.lock() {
+            operation_name.clone_from(&request.operation_name);
+        }
+        next.run(ctx, request).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let operation_name = self.operation_name.lock().ok().and_then(|name| name.clone());
+        let mut operations = document.operations.iter();
+        let operation = match operation_name.as_deref() {
+            Some(wanted) => operations.find(|(name, _)| name.is_some_and(|name| name.as_str() == wanted)),
+            // Without a name the document must hold a single operation
+            None => operations.next().filter(|_| operations.next().is_none()),
+        };
+        if let (Some((_, operation)), Ok(mut operation_type)) = (operation, self.operation_type.lock()) {
+            *operation_type = Some(match operation.node.ty {
+                OperationType::Query => "query",
+                OperationType::Mutation => "mutation",
+                OperationType::Subscription => "subscription",
+            });
+        }
+        Ok(document)
+    }
+
+    async fn resolve(&self, ctx: &ExtensionContext<'_>, info: ResolveInfo<'_>, next: NextResolve<'_>) -> ServerResult<Option<Value>> {
+        // List items are resolved as fields of the list type, the list field is timed already
+        if info.is_for_introspection || info.parent_type.starts_with('[') {
+            return next.run(ctx, info).await;
+        }
+        let field = format!("{}.{}", info.parent_type, info.name);
+        let started = Instant::now();
+        let result = next.run(ctx, info).await;
+        METRICS
+            .resolver_duration
+            .with_label_values(&[&field])
+            .observe(started.elapsed().as_secs_f64());
+        result
+    }
+}
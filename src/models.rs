@@ -1,6 +1,13 @@
-use async_graphql::{SimpleObject, InputObject, ID};
+use async_graphql::connection::{Connection, OpaqueCursor};
+use async_graphql::{Enum, SimpleObject, InputObject, Union, ID};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use crate::address::{mentions_account, Address};
+use crate::balance::Balance;
+use crate::errors::AppError;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
@@ -21,37 +28,1238 @@ pub struct ChainInfo {
 pub struct Event {
     pub id: ID,
     pub block_number: u64,
+    pub event_index: u32, // Position within the block's `System.Events`
     pub extrinsic_id: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub pallet_name: String,
     pub event_name: String,
-    // Store data as JSON string for flexibility, or define specific structures if known
-    pub data: serde_json::Value, 
+    // Store data as JSON string for flexibility, or define specific structures if known.
+    // Account ids are 0x-prefixed hex, resolved as SS58 addresses by `Event.data`.
+    #[graphql(skip)]
+    pub data: serde_json::Value,
+    // False until the block is finalized, events of unfinalized blocks are retracted on reorg
+    pub finalized: bool,
     #[graphql(skip)]
     pub chain_id: ID, // Foreign key to ChainInfo
 }
 
+// Position of an event in the chain, the key events are ordered and paginated by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EventCursor {
+    pub block_number: u64,
+    pub event_index: u32,
+}
+
+impl From<&Event> for EventCursor {
+    fn from(event: &Event) -> Self {
+        Self {
+            block_number: event.block_number,
+            event_index: event.event_index,
+        }
+    }
+}
+
+// Extra fields on `EventConnection`
+#[derive(SimpleObject, Debug, Default)]
+pub struct EventConnectionFields {
+    // Only computed when selected, counting large ranges is not free
+    pub total_count: Option<u64>,
+}
+
+// Relay connection over events, newest first. Cursors are opaque (block_number, event_index) pairs.
+pub type EventConnection = Connection<OpaqueCursor<EventCursor>, Event, EventConnectionFields>;
+
+// Aggregates over the chain's events matching `filter`, each computed only when selected.
+#[derive(SimpleObject, Clone, Debug)]
+#[graphql(complex)]
+pub struct EventStats {
+    pub chain_id: ID,
+    #[graphql(skip)]
+    pub filter: Option<EventFilterInput>,
+}
+
+#[derive(SimpleObject, Clone, Debug, PartialEq, Eq)]
+pub struct EventNameCount {
+    pub pallet_name: String,
+    pub event_name: String,
+    pub count: u64,
+}
+
+// Width of a histogram bucket. Buckets start on UTC boundaries.
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeBucket {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl TimeBucket {
+    pub fn millis(self) -> i64 {
+        match self {
+            TimeBucket::Minute => 60_000,
+            TimeBucket::Hour => 3_600_000,
+            TimeBucket::Day => 86_400_000,
+        }
+    }
+}
+
+#[derive(SimpleObject, Clone, Debug, PartialEq, Eq)]
+pub struct TimeBucketCount {
+    pub start: DateTime<Utc>,
+    pub count: u64,
+}
+
+// Aggregates over the unsigned integers found at a data path, whether stored as numbers
+// or decimal strings. Other values are left out. Results are decimal strings, sums of
+// balances easily exceed 128 bits.
+#[derive(SimpleObject, Clone, Debug, Default)]
+pub struct DataFieldStats {
+    // Events contributing a value
+    pub count: u64,
+    // `None` when no event did
+    pub sum: Option<String>,
+    pub min: Option<String>,
+    pub max: Option<String>,
+}
+
+impl DataFieldStats {
+    // Folds one more value in, values that are not unsigned integers are skipped
+    pub fn add(&mut self, value: &serde_json::Value) {
+        let Some(digits) = unsigned_digits(value) else {
+            return;
+        };
+        self.count += 1;
+        self.sum = Some(match &self.sum {
+            Some(sum) => add_digits(sum, &digits),
+            None => digits.to_string(),
+        });
+        if self.min.as_deref().is_none_or(|min| compare_digits(&digits, min) == Ordering::Less) {
+            self.min = Some(digits.to_string());
+        }
+        if self.max.as_deref().is_none_or(|max| compare_digits(&digits, max) == Ordering::Greater) {
+            self.max = Some(digits.to_string());
+        }
+    }
+}
+
+// A block of the best chain. Only blocks the indexer has seen are stored, blocks that are
+// not finalized yet are replaced when the chain reorganizes.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+#[graphql(complex)]
+pub struct Block {
+    pub number: u64,
+    pub hash: String, // 0x-prefixed hex, as are the other hashes
+    pub parent_hash: String,
+    pub state_root: String,
+    pub extrinsics_root: String,
+    pub timestamp: DateTime<Utc>,
+    // Account of the block producer, `None` when the consensus digest does not identify one
+    #[graphql(skip)] // Resolved as an `Address`, see `Block.author`
+    pub author: Option<String>,
+    pub finalized: bool,
+    #[graphql(skip)]
+    pub chain_id: ID,
+}
+
+// Relay connection over blocks, newest first. Cursors are opaque block numbers.
+pub type BlockConnection = Connection<OpaqueCursor<u64>, Block>;
+
+impl Block {
+    // Finalized block with random hashes, for the mock data generators
+    pub fn mock(chain_id: &ID, number: u64, parent_hash: String, timestamp: DateTime<Utc>) -> Self {
+        let random_hash = || format!("0x{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        Self {
+            number,
+            hash: random_hash(),
+            parent_hash,
+            state_root: random_hash(),
+            extrinsics_root: random_hash(),
+            timestamp,
+            author: None,
+            finalized: true,
+            chain_id: chain_id.clone(),
+        }
+    }
+}
+
+// A call included in a block, signed or not. Ids are `<block_number>-<index>`, the
+// same value events carry in `extrinsic_id`.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+#[graphql(complex)]
+pub struct Extrinsic {
+    pub id: ID,
+    pub block_number: u64,
+    pub index: u32, // Position in the block body
+    pub hash: String,
+    // Signing account, `None` for inherents and other unsigned extrinsics
+    #[graphql(skip)] // Resolved as an `Address`, see `Extrinsic.signer`
+    pub signer: Option<String>,
+    pub nonce: Option<u64>,
+    // Balances are decimal strings in the chain's smallest unit
+    #[graphql(skip)] // Resolved as an `Amount`, see `Extrinsic.tip`
+    pub tip: Option<String>,
+    #[graphql(skip)] // From `TransactionPayment.TransactionFeePaid`, see `Extrinsic.fee`
+    pub fee: Option<String>,
+    pub success: bool,
+    pub pallet_name: String,
+    pub call_name: String,
+    #[graphql(skip)] // Resolved with SS58 addresses, see `Extrinsic.args`
+    pub args: serde_json::Value,
+    pub finalized: bool,
+    #[graphql(skip)]
+    pub chain_id: ID,
+}
+
+impl Extrinsic {
+    pub fn id_for(block_number: u64, index: u32) -> ID {
+        ID::from(format!("{}-{}", block_number, index))
+    }
+
+    // `tip` or `fee` as an `Amount`, `None` if unset or not a decimal string
+    pub fn amount(&self, balance: Option<&str>) -> Option<Amount> {
+        let value = Balance::from_json(&serde_json::Value::String(balance?.to_string()))?;
+        Some(Amount { value, chain_id: self.chain_id.clone() })
+    }
+
+    // Successful finalized extrinsic with a random hash, for the mock data generators
+    pub fn mock(chain_id: &ID, block_number: u64, index: u32, signer: Option<&str>, call: (&str, &str), args: serde_json::Value) -> Self {
+        Self {
+            id: Self::id_for(block_number, index),
+            block_number,
+            index,
+            hash: format!("0x{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+            signer: signer.map(str::to_string),
+            nonce: signer.map(|_| 0),
+            tip: signer.map(|_| "0".to_string()),
+            fee: signer.map(|_| "125000000".to_string()),
+            success: true,
+            pallet_name: call.0.to_string(),
+            call_name: call.1.to_string(),
+            args,
+            finalized: true,
+            chain_id: chain_id.clone(),
+        }
+    }
+}
+
+// Position of an extrinsic in the chain
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ExtrinsicCursor {
+    pub block_number: u64,
+    pub index: u32,
+}
+
+impl From<&Extrinsic> for ExtrinsicCursor {
+    fn from(extrinsic: &Extrinsic) -> Self {
+        Self {
+            block_number: extrinsic.block_number,
+            index: extrinsic.index,
+        }
+    }
+}
+
+// Relay connection over extrinsics, newest first. Cursors are opaque (block_number, index) pairs.
+pub type ExtrinsicConnection = Connection<OpaqueCursor<ExtrinsicCursor>, Extrinsic>;
+
+// What an account did on one chain. Any event whose data holds the account id counts as
+// activity, as does signing an extrinsic.
+#[derive(SimpleObject, Clone, Debug)]
+#[graphql(complex)]
+pub struct Account {
+    #[graphql(skip)] // Resolved in the chain's format, see `Account.address`
+    pub address: Address,
+    pub chain_id: ID,
+}
+
+// Which side of a transfer an account is on
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TransferDirection {
+    Incoming,
+    Outgoing,
+    #[default]
+    All,
+}
+
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackfillState {
+    Running,
+    // Waiting to retry after an error, see `BackfillStatus.error`
+    Failed,
+    Completed,
+    // Stopped by an admin, not resumed on restart
+    Cancelled,
+}
+
+impl BackfillState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Failed => "failed",
+            Self::Completed => "completed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "running" => Some(Self::Running),
+            "failed" => Some(Self::Failed),
+            "completed" => Some(Self::Completed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+// Progress of a historical backfill over `from_block..=to_block`
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+#[graphql(complex)]
+pub struct BackfillStatus {
+    pub from_block: u64,
+    pub to_block: u64,
+    // Checkpoint: every block of the range below this one is indexed
+    pub next_block: u64,
+    pub state: BackfillState,
+    pub error: Option<String>, // Last failure, cleared on retry
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[graphql(skip)]
+    pub chain_id: ID,
+}
+
+// What feeds a chain's blocks and events into the store
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum WorkerKind {
+    // Follows the chain's Substrate node
+    Ingestion,
+    // Generates mock blocks and events
+    Simulator,
+}
+
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum WorkerState {
+    Running,
+    // Idle but keeps its place, starting it again resumes from there
+    Paused,
+    Stopped,
+}
+
+#[derive(SimpleObject, Clone, Debug, Serialize)]
+pub struct WorkerStatus {
+    pub chain_id: ID,
+    pub kind: WorkerKind,
+    pub state: WorkerState,
+}
+
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum HealthState {
+    Pass,
+    // Ready, but something needs a look, see `BroadcastHealth`
+    Warn,
+    // Not ready
+    Fail,
+}
+
+// What `healthCheck`, /healthz and /readyz report
+#[derive(SimpleObject, Clone, Debug, Serialize)]
+pub struct HealthStatus {
+    pub status: HealthState,
+    // False once a running chain's worker task died, only a restart brings it back
+    pub live: bool,
+    // False while not live, the store is unreachable or a chain lags too far behind its node
+    pub ready: bool,
+    pub store: StoreHealth,
+    pub broadcast: BroadcastHealth,
+    pub chains: Vec<ChainHealth>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(SimpleObject, Clone, Debug, Serialize)]
+pub struct StoreHealth {
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+// The channel fanning events out to subscribers. A full channel means the slowest
+// subscriber lags and is replaying from the store.
+#[derive(SimpleObject, Clone, Debug, Serialize)]
+pub struct BroadcastHealth {
+    pub subscribers: usize,
+    pub queued: usize,
+    pub capacity: usize,
+}
+
+#[derive(SimpleObject, Clone, Debug, Serialize)]
+pub struct ChainHealth {
+    pub chain_id: ID,
+    // None when nothing feeds the chain
+    pub worker: Option<WorkerStatus>,
+    // False when the worker's task ended without being stopped, e.g. it panicked
+    pub worker_alive: bool,
+    // Best block the node reported to ingestion
+    pub node_head: Option<u64>,
+    pub indexed_height: Option<u64>,
+    pub finalized_height: Option<u64>,
+    // Blocks the store is behind the node, while ingesting
+    pub lag_blocks: Option<u64>,
+    pub ready: bool,
+}
+
+// A chain to add to the registry at runtime, see `ChainConfig` for the fields
+#[derive(InputObject, Clone, Debug)]
+pub struct RegisterChainInput {
+    pub id: ID,
+    pub name: String,
+    pub endpoint: String,
+    pub ss58_prefix: u16,
+    pub token_symbol: String,
+    pub decimals: u8,
+    // Backfill from this block up to the finalized head once registered
+    pub backfill_from_block: Option<u64>,
+}
+
+// What a caller may do. `Admin` implies every other scope.
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    // Queries
+    Read,
+    // Subscriptions
+    Subscribe,
+    // Mutations
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Subscribe => "subscribe",
+            Scope::Admin => "admin",
+        }
+    }
 
-#[derive(InputObject, Debug)]
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(Scope::Read),
+            "subscribe" => Some(Scope::Subscribe),
+            "admin" => Some(Scope::Admin),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMethod {
+    Anonymous,
+    ApiKey,
+    Jwt,
+}
+
+// Who sent the request, see `viewer`
+#[derive(SimpleObject, Clone, Debug)]
+pub struct Caller {
+    // The JWT's `sub`, the API key's name, or "anonymous"
+    pub subject: String,
+    pub method: AuthMethod,
+    pub scopes: Vec<Scope>,
+}
+
+impl Caller {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.iter().any(|s| *s == scope || *s == Scope::Admin)
+    }
+}
+
+// A freshly issued API key. The key itself is only ever returned here.
+#[derive(SimpleObject, Clone, Debug)]
+pub struct RotatedApiKey {
+    pub id: ID,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// Filter over extrinsics. All set fields must hold.
+#[derive(InputObject, Debug, Default, Clone)]
+pub struct ExtrinsicFilterInput {
+    pub pallet_name_eq: Option<String>,
+    pub pallet_name_in: Option<Vec<String>>,
+    pub call_name_eq: Option<String>,
+    pub call_name_in: Option<Vec<String>>,
+    pub signer_eq: Option<Address>,
+    pub signed: Option<bool>,
+    pub success: Option<bool>,
+    pub block_number_gte: Option<u64>,
+    pub block_number_lte: Option<u64>,
+    pub finalized: Option<bool>,
+}
+
+impl ExtrinsicFilterInput {
+    pub fn matches(&self, extrinsic: &Extrinsic) -> bool {
+        let text = |value: &str, eq: &Option<String>, any_of: &Option<Vec<String>>| {
+            eq.as_ref().is_none_or(|eq| eq == value) && any_of.as_ref().is_none_or(|any| any.iter().any(|v| v == value))
+        };
+        text(&extrinsic.pallet_name, &self.pallet_name_eq, &self.pallet_name_in)
+            && text(&extrinsic.call_name, &self.call_name_eq, &self.call_name_in)
+            && self.signer_eq.is_none_or(|s| extrinsic.signer.as_ref() == Some(&s.to_hex()))
+            && self.signed.is_none_or(|signed| extrinsic.signer.is_some() == signed)
+            && self.success.is_none_or(|success| extrinsic.success == success)
+            && self.block_number_gte.is_none_or(|bn| extrinsic.block_number >= bn)
+            && self.block_number_lte.is_none_or(|bn| extrinsic.block_number <= bn)
+            && self.finalized.is_none_or(|finalized| extrinsic.finalized == finalized)
+    }
+}
+
+// Filter over events. All set fields must hold; `and`, `or` and `not` nest further filters.
+// String operators are case-sensitive. Events without an extrinsic never match the
+// `extrinsic_id_*` string operators, use `extrinsic_id_is_null` to select them.
+#[derive(InputObject, Debug, Default, Clone)]
 pub struct EventFilterInput {
     pub pallet_name_eq: Option<String>,
+    pub pallet_name_in: Option<Vec<String>>,
+    pub pallet_name_nin: Option<Vec<String>>,
+    pub pallet_name_starts_with: Option<String>,
+    pub pallet_name_contains: Option<String>,
     pub event_name_eq: Option<String>,
+    pub event_name_in: Option<Vec<String>>,
+    pub event_name_nin: Option<Vec<String>>,
+    pub event_name_starts_with: Option<String>,
+    pub event_name_contains: Option<String>,
+    pub extrinsic_id_eq: Option<String>,
+    pub extrinsic_id_in: Option<Vec<String>>,
+    pub extrinsic_id_nin: Option<Vec<String>>,
+    pub extrinsic_id_starts_with: Option<String>,
+    pub extrinsic_id_is_null: Option<bool>,
     pub block_number_gte: Option<u64>,
     pub block_number_lte: Option<u64>,
+    pub timestamp_gte: Option<DateTime<Utc>>,
+    pub timestamp_lte: Option<DateTime<Utc>>,
+    pub finalized: Option<bool>,
+    // Events whose data holds this account id, in any field
+    pub account: Option<Address>,
+    // Conditions on the decoded `data` payload
+    pub data: Option<Vec<EventDataFilterInput>>,
+    // An empty list imposes no constraint
+    pub and: Option<Vec<EventFilterInput>>,
+    // An empty list imposes no constraint
+    pub or: Option<Vec<EventFilterInput>>,
+    pub not: Option<Box<EventFilterInput>>,
 }
 
-// Example of how you might represent some event data more concretely
-#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
-pub struct TransferEventData {
-    from: String, // Typically an AccountId string
-    to: String,   // Typically an AccountId string
-    amount: u128,
-}
-
-// Placeholder for what might come from an indexer
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct RawEventData {
-    pub pallet: String,
-    pub variant: String,
-    pub fields: serde_json::Value, // This could be a map or a list depending on the event
-} 
\ No newline at end of file
+// Condition on a value inside `Event.data`. Paths are dot separated and numeric segments
+// index arrays, e.g. `to`, `dispatch_info.weight.ref_time` or `0` for unnamed fields.
+// Numeric operators compare unsigned integers of any size, whether the payload holds them
+// as JSON numbers or as decimal strings (how u128 balances are stored). Other values never
+// match them.
+#[derive(InputObject, Debug, Clone)]
+pub struct EventDataFilterInput {
+    pub path: String,
+    // Scalar only. Unsigned integers compare by value, so `"1000"` matches `1000`
+    pub eq: Option<serde_json::Value>,
+    pub gt: Option<String>,
+    pub gte: Option<String>,
+    pub lt: Option<String>,
+    pub lte: Option<String>,
+    // A present key holding `null` exists
+    pub exists: Option<bool>,
+}
+
+// Nesting limit for `and`/`or`/`not`
+pub const MAX_EVENT_FILTER_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub enum EventTextField {
+    PalletName,
+    EventName,
+    ExtrinsicId,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TextOp<'a> {
+    Eq(&'a str),
+    In(&'a [String]),
+    Nin(&'a [String]),
+    StartsWith(&'a str),
+    Contains(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+#[derive(Debug, Clone)]
+pub enum DataOp<'a> {
+    Exists(bool),
+    // Equality of a non-numeric scalar, compared as JSON
+    EqJson(Cow<'a, serde_json::Value>),
+    // Comparison against a canonical unsigned integer (decimal digits, no leading zeros)
+    Numeric(CompareOp, Cow<'a, str>),
+}
+
+// A single column condition of a filter. Backends translate these one by one so every
+// backend evaluates the same conditions.
+#[derive(Debug, Clone)]
+pub enum EventPredicate<'a> {
+    Text(EventTextField, TextOp<'a>),
+    Data(Vec<PathSegment<'a>>, DataOp<'a>),
+    ExtrinsicIdIsNull(bool),
+    BlockNumberGte(u64),
+    BlockNumberLte(u64),
+    // Compared at millisecond precision, which is what backends persist
+    TimestampGte(i64),
+    TimestampLte(i64),
+    Finalized(bool),
+    // Lowercase hex account id, see `mentions_account`
+    Account(String),
+}
+
+impl EventFilterInput {
+    // The flat conditions of this level, without the nested combinators.
+    pub fn predicates(&self) -> Vec<EventPredicate<'_>> {
+        use EventPredicate::*;
+        use EventTextField::*;
+        let mut predicates = Vec::new();
+        let text_ops = [
+            (PalletName, &self.pallet_name_eq, &self.pallet_name_in, &self.pallet_name_nin, &self.pallet_name_starts_with, &self.pallet_name_contains),
+            (EventName, &self.event_name_eq, &self.event_name_in, &self.event_name_nin, &self.event_name_starts_with, &self.event_name_contains),
+            (ExtrinsicId, &self.extrinsic_id_eq, &self.extrinsic_id_in, &self.extrinsic_id_nin, &self.extrinsic_id_starts_with, &None),
+        ];
+        for (field, eq, is_in, nin, starts_with, contains) in text_ops {
+            if let Some(v) = eq {
+                predicates.push(Text(field, TextOp::Eq(v)));
+            }
+            if let Some(v) = is_in {
+                predicates.push(Text(field, TextOp::In(v)));
+            }
+            if let Some(v) = nin {
+                predicates.push(Text(field, TextOp::Nin(v)));
+            }
+            if let Some(v) = starts_with {
+                predicates.push(Text(field, TextOp::StartsWith(v)));
+            }
+            if let Some(v) = contains {
+                predicates.push(Text(field, TextOp::Contains(v)));
+            }
+        }
+        if let Some(is_null) = self.extrinsic_id_is_null {
+            predicates.push(ExtrinsicIdIsNull(is_null));
+        }
+        if let Some(bn_gte) = self.block_number_gte {
+            predicates.push(BlockNumberGte(bn_gte));
+        }
+        if let Some(bn_lte) = self.block_number_lte {
+            predicates.push(BlockNumberLte(bn_lte));
+        }
+        if let Some(ts_gte) = self.timestamp_gte {
+            predicates.push(TimestampGte(ts_gte.timestamp_millis()));
+        }
+        if let Some(ts_lte) = self.timestamp_lte {
+            predicates.push(TimestampLte(ts_lte.timestamp_millis()));
+        }
+        if let Some(finalized) = self.finalized {
+            predicates.push(Finalized(finalized));
+        }
+        if let Some(account) = self.account {
+            predicates.push(Account(account.to_hex()));
+        }
+        for condition in self.data.iter().flatten() {
+            // Invalid conditions are rejected by `validate`
+            let Some(path) = parse_data_path(&condition.path) else {
+                continue;
+            };
+            if let Some(exists) = condition.exists {
+                predicates.push(Data(path.clone(), DataOp::Exists(exists)));
+            }
+            if let Some(eq) = &condition.eq {
+                let op = match (unsigned_digits(eq), eq.as_str().and_then(|s| Address::parse(s).ok())) {
+                    (Some(digits), _) => DataOp::Numeric(CompareOp::Eq, digits),
+                    // Accounts are stored as hex, whatever format they are given in
+                    (None, Some(address)) => DataOp::EqJson(Cow::Owned(serde_json::Value::String(address.to_hex()))),
+                    (None, None) => DataOp::EqJson(Cow::Borrowed(eq)),
+                };
+                predicates.push(Data(path.clone(), op));
+            }
+            let bounds = [
+                (CompareOp::Gt, &condition.gt),
+                (CompareOp::Gte, &condition.gte),
+                (CompareOp::Lt, &condition.lt),
+                (CompareOp::Lte, &condition.lte),
+            ];
+            for (op, bound) in bounds {
+                if let Some(digits) = bound.as_deref().and_then(canonical_digits) {
+                    predicates.push(Data(path.clone(), DataOp::Numeric(op, Cow::Borrowed(digits))));
+                }
+            }
+        }
+        predicates
+    }
+
+    // Reject filters the backends cannot evaluate.
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.depth() > MAX_EVENT_FILTER_DEPTH {
+            return Err(AppError::InvalidInput(format!("Filter nesting exceeds {} levels", MAX_EVENT_FILTER_DEPTH)));
+        }
+        self.validate_data()
+    }
+
+    fn validate_data(&self) -> Result<(), AppError> {
+        for condition in self.data.iter().flatten() {
+            if parse_data_path(&condition.path).is_none() {
+                return Err(AppError::InvalidInput(format!("Invalid data path: {:?}", condition.path)));
+            }
+            if matches!(condition.eq, Some(serde_json::Value::Array(_) | serde_json::Value::Object(_))) {
+                return Err(AppError::InvalidInput(format!("`eq` on {} must be a scalar", condition.path)));
+            }
+            for bound in [&condition.gt, &condition.gte, &condition.lt, &condition.lte].into_iter().flatten() {
+                if canonical_digits(bound).is_none() {
+                    return Err(AppError::InvalidInput(format!("Numeric bound must be an unsigned integer, got {:?}", bound)));
+                }
+            }
+        }
+        self.and
+            .iter()
+            .chain(self.or.iter())
+            .flatten()
+            .chain(self.not.as_deref())
+            .try_for_each(EventFilterInput::validate_data)
+    }
+
+    // `filter AND data`, used to fold the `dataFilter` argument into the main filter.
+    pub fn with_data(filter: Option<Self>, data: Option<Vec<EventDataFilterInput>>) -> Option<Self> {
+        match data {
+            None => filter,
+            Some(data) => Some(Self {
+                data: Some(data),
+                and: filter.map(|f| vec![f]),
+                ..Self::default()
+            }),
+        }
+    }
+
+    // `filter AND finalized`, used to fold the `finalizedOnly` argument into the main filter.
+    pub fn with_finalized_only(filter: Option<Self>, finalized_only: bool) -> Option<Self> {
+        if !finalized_only {
+            return filter;
+        }
+        Some(Self {
+            finalized: Some(true),
+            and: filter.map(|f| vec![f]),
+            ..Self::default()
+        })
+    }
+
+    // `filter AND account`, the account's events matching `filter`.
+    pub fn with_account(filter: Option<Self>, account: Address) -> Self {
+        Self {
+            account: Some(account),
+            and: filter.map(|f| vec![f]),
+            ..Self::default()
+        }
+    }
+
+    // `Balances.Transfer` events moving funds from or to `account`, which the account index
+    // narrows down before the `from`/`to` fields are checked.
+    pub fn transfers(account: Address, direction: TransferDirection) -> Self {
+        let side = |path: &str| EventDataFilterInput {
+            path: path.to_string(),
+            eq: Some(serde_json::Value::String(account.to_hex())),
+            gt: None,
+            gte: None,
+            lt: None,
+            lte: None,
+            exists: None,
+        };
+        let data = match direction {
+            TransferDirection::Incoming => Some(vec![side("to")]),
+            TransferDirection::Outgoing => Some(vec![side("from")]),
+            TransferDirection::All => None,
+        };
+        Self {
+            pallet_name_eq: Some("Balances".to_string()),
+            event_name_eq: Some("Transfer".to_string()),
+            account: Some(account),
+            data,
+            ..Self::default()
+        }
+    }
+
+    // Nesting depth of the combinators, a filter without any is depth 1.
+    pub fn depth(&self) -> usize {
+        let nested = self
+            .and
+            .iter()
+            .chain(self.or.iter())
+            .flatten()
+            .chain(self.not.as_deref())
+            .map(EventFilterInput::depth)
+            .max()
+            .unwrap_or(0);
+        nested + 1
+    }
+
+    // In-process evaluation of the filter, shared by storage backends that can't push it down.
+    pub fn matches(&self, event: &Event) -> bool {
+        self.predicates().iter().all(|p| p.matches(event))
+            && self.and.iter().flatten().all(|f| f.matches(event))
+            && self.or.as_ref().is_none_or(|or| or.is_empty() || or.iter().any(|f| f.matches(event)))
+            && self.not.as_ref().is_none_or(|not| !not.matches(event))
+    }
+}
+
+impl EventPredicate<'_> {
+    pub fn matches(&self, event: &Event) -> bool {
+        match *self {
+            EventPredicate::Data(ref path, ref op) => {
+                let value = lookup_data_path(&event.data, path);
+                match op {
+                    DataOp::Exists(exists) => value.is_some() == *exists,
+                    DataOp::EqJson(expected) => value == Some(expected.as_ref()),
+                    DataOp::Numeric(op, bound) => value
+                        .and_then(unsigned_digits)
+                        .is_some_and(|digits| op.holds(compare_digits(&digits, bound))),
+                }
+            }
+            EventPredicate::Text(field, op) => {
+                let value = match field {
+                    EventTextField::PalletName => Some(event.pallet_name.as_str()),
+                    EventTextField::EventName => Some(event.event_name.as_str()),
+                    EventTextField::ExtrinsicId => event.extrinsic_id.as_deref(),
+                };
+                value.is_some_and(|value| op.matches(value))
+            }
+            EventPredicate::ExtrinsicIdIsNull(is_null) => event.extrinsic_id.is_none() == is_null,
+            EventPredicate::BlockNumberGte(bn) => event.block_number >= bn,
+            EventPredicate::BlockNumberLte(bn) => event.block_number <= bn,
+            EventPredicate::TimestampGte(ms) => event.timestamp.timestamp_millis() >= ms,
+            EventPredicate::TimestampLte(ms) => event.timestamp.timestamp_millis() <= ms,
+            EventPredicate::Finalized(finalized) => event.finalized == finalized,
+            EventPredicate::Account(ref account) => mentions_account(&event.data, account),
+        }
+    }
+}
+
+impl CompareOp {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Gte => ordering != Ordering::Less,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Lte => ordering != Ordering::Greater,
+        }
+    }
+}
+
+// `a.b.0` -> [Key("a"), Key("b"), Index(0)]. An optional `$.` prefix is accepted. Segments
+// must be non-empty and may not contain `"`, which the SQLite JSON path could not quote.
+pub fn parse_data_path(path: &str) -> Option<Vec<PathSegment<'_>>> {
+    let path = path.strip_prefix("$.").unwrap_or(path);
+    path.split('.')
+        .map(|segment| {
+            if segment.is_empty() || segment.contains('"') {
+                None
+            } else if let Some(index) = canonical_digits(segment).and_then(|d| d.parse().ok()) {
+                Some(PathSegment::Index(index))
+            } else {
+                Some(PathSegment::Key(segment))
+            }
+        })
+        .collect()
+}
+
+pub fn lookup_data_path<'v>(data: &'v serde_json::Value, path: &[PathSegment<'_>]) -> Option<&'v serde_json::Value> {
+    path.iter().try_fold(data, |value, segment| match segment {
+        PathSegment::Key(key) => value.as_object()?.get(*key),
+        PathSegment::Index(index) => value.as_array()?.get(*index),
+    })
+}
+
+// Decimal digits without leading zeros, the form integers are compared in.
+fn canonical_digits(s: &str) -> Option<&str> {
+    let canonical = !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) && (s == "0" || !s.starts_with('0'));
+    canonical.then_some(s)
+}
+
+fn unsigned_digits(value: &serde_json::Value) -> Option<Cow<'_, str>> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().map(|n| Cow::Owned(n.to_string())),
+        serde_json::Value::String(s) => canonical_digits(s).map(Cow::Borrowed),
+        _ => None,
+    }
+}
+
+// Sum of two canonical digit strings, of any length.
+fn add_digits(a: &str, b: &str) -> String {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let digit = |digits: &[u8], position: usize| match digits.len().checked_sub(position + 1) {
+        Some(index) => digits[index] - b'0',
+        None => 0,
+    };
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for position in 0..a.len().max(b.len()) {
+        let total = digit(a, position) + digit(b, position) + carry;
+        sum.push(b'0' + total % 10);
+        carry = total / 10;
+    }
+    if carry > 0 {
+        sum.push(b'0' + carry);
+    }
+    sum.iter().rev().map(|d| char::from(*d)).collect()
+}
+
+// Canonical digit strings order by length first, then lexicographically.
+fn compare_digits(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+impl TextOp<'_> {
+    fn matches(&self, value: &str) -> bool {
+        match *self {
+            TextOp::Eq(v) => value == v,
+            TextOp::In(values) => values.iter().any(|v| v == value),
+            TextOp::Nin(values) => !values.iter().any(|v| v == value),
+            TextOp::StartsWith(prefix) => value.starts_with(prefix),
+            TextOp::Contains(needle) => value.contains(needle),
+        }
+    }
+}
+
+// Runtime metadata of a chain, decoded from `state_getMetadata` (V14 or V15). Served
+// through `SharedRuntimeMetadata`.
+#[derive(Clone, Debug)]
+pub struct RuntimeMetadata {
+    pub spec_version: u32,
+    pub metadata_version: u32,
+    pub pallets: Vec<PalletMetadata>,
+}
+
+// The service's cached copy, resolved without cloning its pallets
+#[derive(Clone, Debug)]
+pub struct SharedRuntimeMetadata(pub Arc<RuntimeMetadata>);
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct PalletMetadata {
+    pub name: String,
+    pub index: u8,
+    pub docs: Vec<String>, // Only populated from V15 metadata
+    pub calls: Vec<CallMetadata>,
+    pub events: Vec<EventMetadata>,
+    pub storage: Vec<StorageItemMetadata>,
+    pub constants: Vec<ConstantMetadata>,
+    pub errors: Vec<ErrorMetadata>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct FieldMetadata {
+    pub name: Option<String>,
+    pub type_name: Option<String>, // Type as written in the pallet source, e.g. `T::Balance`
+    pub type_id: u32,              // Id in the runtime type registry
+    pub resolved_type: String,     // Rendered registry type, e.g. `u128`
+    pub docs: Vec<String>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct CallMetadata {
+    pub name: String,
+    pub index: u8,
+    pub fields: Vec<FieldMetadata>,
+    pub docs: Vec<String>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct EventMetadata {
+    pub name: String,
+    pub index: u8,
+    pub fields: Vec<FieldMetadata>,
+    pub docs: Vec<String>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct ErrorMetadata {
+    pub name: String,
+    pub index: u8,
+    pub docs: Vec<String>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct StorageItemMetadata {
+    pub name: String,
+    pub prefix: String,
+    pub modifier: String, // "Optional" or "Default"
+    pub hashers: Vec<String>,
+    pub key_type: Option<String>, // None for plain (non-map) entries
+    pub value_type: String,
+    pub docs: Vec<String>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct ConstantMetadata {
+    pub name: String,
+    pub resolved_type: String,
+    pub value: String, // SCALE encoded, 0x-prefixed hex
+    pub decoded: serde_json::Value,
+    pub docs: Vec<String>,
+}
+
+// Typed payload of an event, for the events of well-known pallets. Others, and ones whose
+// data does not have the expected shape, are `GenericEventData`.
+#[derive(Union, Clone, Debug)]
+pub enum DecodedEvent {
+    BalancesTransfer(BalancesTransfer),
+    BalancesDeposit(BalancesDeposit),
+    BalancesWithdraw(BalancesWithdraw),
+    BalancesEndowed(BalancesEndowed),
+    SystemNewAccount(SystemNewAccount),
+    SystemKilledAccount(SystemKilledAccount),
+    TimestampSet(TimestampSet),
+    TransactionPaymentTransactionFeePaid(TransactionPaymentTransactionFeePaid),
+    GenericEventData(GenericEventData),
+}
+
+// An amount of the chain's native token
+#[derive(SimpleObject, Clone, Debug)]
+#[graphql(complex)]
+pub struct Amount {
+    // In the smallest unit, see `Amount.formatted` for whole tokens
+    pub value: Balance,
+    #[graphql(skip)]
+    pub chain_id: ID,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct BalancesTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub amount: Amount,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct BalancesDeposit {
+    pub who: Address,
+    pub amount: Amount,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct BalancesWithdraw {
+    pub who: Address,
+    pub amount: Amount,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct BalancesEndowed {
+    pub account: Address,
+    pub free_balance: Amount,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct SystemNewAccount {
+    pub account: Address,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct SystemKilledAccount {
+    pub account: Address,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct TimestampSet {
+    pub now: DateTime<Utc>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct TransactionPaymentTransactionFeePaid {
+    pub who: Address,
+    pub actual_fee: Amount,
+    pub tip: Amount,
+}
+
+// Payload of an event without a typed counterpart
+#[derive(SimpleObject, Clone, Debug)]
+pub struct GenericEventData {
+    pub pallet_name: String,
+    pub event_name: String,
+    pub fields: Vec<EventField>,
+}
+
+#[derive(SimpleObject, Clone, Debug)]
+pub struct EventField {
+    // `None` for unnamed fields, which keep their order
+    pub name: Option<String>,
+    pub value: serde_json::Value,
+}
+
+// What a field of a known event holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnownField {
+    Account,
+    Amount,
+    // Milliseconds since the epoch
+    Timestamp,
+}
+
+// An event with a typed `DecodedEvent` payload. `fields` are in declaration order, data
+// stored as an array holds them by position. Account fields also tell `AccountFields`
+// where the accounts are for chains without runtime metadata.
+pub struct KnownEvent {
+    pub pallet_name: &'static str,
+    pub event_name: &'static str,
+    pub fields: &'static [(&'static str, KnownField)],
+    build: fn(&KnownEventData<'_>) -> Option<DecodedEvent>,
+}
+
+pub const KNOWN_EVENTS: &[KnownEvent] = &[
+    KnownEvent {
+        pallet_name: "Balances",
+        event_name: "Transfer",
+        fields: &[("from", KnownField::Account), ("to", KnownField::Account), ("amount", KnownField::Amount)],
+        build: |data| {
+            Some(DecodedEvent::BalancesTransfer(BalancesTransfer {
+                from: data.address("from")?,
+                to: data.address("to")?,
+                amount: data.amount("amount")?,
+            }))
+        },
+    },
+    KnownEvent {
+        pallet_name: "Balances",
+        event_name: "Deposit",
+        fields: &[("who", KnownField::Account), ("amount", KnownField::Amount)],
+        build: |data| {
+            Some(DecodedEvent::BalancesDeposit(BalancesDeposit {
+                who: data.address("who")?,
+                amount: data.amount("amount")?,
+            }))
+        },
+    },
+    KnownEvent {
+        pallet_name: "Balances",
+        event_name: "Withdraw",
+        fields: &[("who", KnownField::Account), ("amount", KnownField::Amount)],
+        build: |data| {
+            Some(DecodedEvent::BalancesWithdraw(BalancesWithdraw {
+                who: data.address("who")?,
+                amount: data.amount("amount")?,
+            }))
+        },
+    },
+    KnownEvent {
+        pallet_name: "Balances",
+        event_name: "Endowed",
+        fields: &[("account", KnownField::Account), ("free_balance", KnownField::Amount)],
+        build: |data| {
+            Some(DecodedEvent::BalancesEndowed(BalancesEndowed {
+                account: data.address("account")?,
+                free_balance: data.amount("free_balance")?,
+            }))
+        },
+    },
+    KnownEvent {
+        pallet_name: "System",
+        event_name: "NewAccount",
+        fields: &[("account", KnownField::Account)],
+        build: |data| Some(DecodedEvent::SystemNewAccount(SystemNewAccount { account: data.address("account")? })),
+    },
+    KnownEvent {
+        pallet_name: "System",
+        event_name: "KilledAccount",
+        fields: &[("account", KnownField::Account)],
+        build: |data| Some(DecodedEvent::SystemKilledAccount(SystemKilledAccount { account: data.address("account")? })),
+    },
+    // Emitted by the simulator only, the real pallet has no events
+    KnownEvent {
+        pallet_name: "Timestamp",
+        event_name: "TimestampSet",
+        fields: &[("now", KnownField::Timestamp)],
+        build: |data| Some(DecodedEvent::TimestampSet(TimestampSet { now: data.timestamp("now")? })),
+    },
+    KnownEvent {
+        pallet_name: "TransactionPayment",
+        event_name: "TransactionFeePaid",
+        fields: &[("who", KnownField::Account), ("actual_fee", KnownField::Amount), ("tip", KnownField::Amount)],
+        build: |data| {
+            Some(DecodedEvent::TransactionPaymentTransactionFeePaid(TransactionPaymentTransactionFeePaid {
+                who: data.address("who")?,
+                actual_fee: data.amount("actual_fee")?,
+                tip: data.amount("tip")?,
+            }))
+        },
+    },
+];
+
+// Stored data of a known event, read field by field
+pub struct KnownEventData<'a> {
+    event: &'a KnownEvent,
+    data: &'a serde_json::Value,
+    chain_id: &'a ID,
+    format: u16,
+}
+
+impl KnownEventData<'_> {
+    fn field(&self, name: &str) -> Option<&serde_json::Value> {
+        match self.data {
+            serde_json::Value::Object(fields) => fields.get(name),
+            serde_json::Value::Array(fields) => fields.get(self.event.fields.iter().position(|(field, _)| *field == name)?),
+            _ => None,
+        }
+    }
+
+    fn address(&self, name: &str) -> Option<Address> {
+        let account = self.field(name)?.as_str()?;
+        Address::parse(account).ok().map(|address| address.with_format(self.format))
+    }
+
+    fn amount(&self, name: &str) -> Option<Amount> {
+        let value = Balance::from_json(self.field(name)?)?;
+        Some(Amount { value, chain_id: self.chain_id.clone() })
+    }
+
+    fn timestamp(&self, name: &str) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(self.field(name)?.as_i64()?)
+    }
+}
+
+impl DecodedEvent {
+    // The typed payload of `data`, stored as indexed, with account ids rendered in `format`.
+    // `None` when there is none for the event or `data` does not fit it.
+    pub fn decode(chain_id: &ID, pallet_name: &str, event_name: &str, data: &serde_json::Value, format: u16) -> Option<Self> {
+        let event = KNOWN_EVENTS
+            .iter()
+            .find(|e| e.pallet_name == pallet_name && e.event_name == event_name)?;
+        (event.build)(&KnownEventData { event, data, chain_id, format })
+    }
+}
+
+impl GenericEventData {
+    // Named fields come from objects, unnamed ones from arrays. Any other payload is a
+    // single unnamed field.
+    pub fn new(pallet_name: &str, event_name: &str, data: serde_json::Value) -> Self {
+        let fields = match data {
+            serde_json::Value::Object(fields) => fields
+                .into_iter()
+                .map(|(name, value)| EventField { name: Some(name), value })
+                .collect(),
+            serde_json::Value::Array(values) => values.into_iter().map(|value| EventField { name: None, value }).collect(),
+            value => vec![EventField { name: None, value }],
+        };
+        Self {
+            pallet_name: pallet_name.to_string(),
+            event_name: event_name.to_string(),
+            fields,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::config::LimitsConfig;
+use crate::errors::{AppError, Result};
+use crate::models::{AuthMethod, Caller};
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest};
+use async_graphql::{Request, ServerResult};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// Buckets kept at most. Full ones are forgotten first, a full bucket is the same as a new
+// one, then the longest idle.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+// Whom an operation is rate limited as: authenticated callers by identity, anonymous
+// ones by IP address. Request data over HTTP, connection data over WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientKey(String);
+
+impl ClientKey {
+    pub fn new(caller: &Caller, peer_addr: Option<SocketAddr>) -> Self {
+        match caller.method {
+            AuthMethod::Anonymous => match peer_addr {
+                Some(addr) => Self(format!("ip:{}", addr.ip())),
+                None => Self("ip:unknown".to_string()),
+            },
+            AuthMethod::ApiKey => Self(format!("api-key:{}", caller.subject)),
+            AuthMethod::Jwt => Self(format!("jwt:{}", caller.subject)),
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+// Token buckets per client, each operation takes one token.
+#[derive(Clone)]
+pub struct RateLimiter {
+    per_sec: f64,
+    burst: f64,
+    buckets: Arc<Mutex<HashMap<ClientKey, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &LimitsConfig) -> Self {
+        Self {
+            per_sec: config.rate_limit_per_sec,
+            burst: f64::from(config.rate_limit_burst.max(1)),
+            buckets: Arc::default(),
+        }
+    }
+
+    pub fn check(&self, client: &ClientKey) -> Result<()> {
+        if self.per_sec <= 0.0 {
+            return Ok(());
+        }
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock rate limiter: {}", e)))?;
+        let now = Instant::now();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.burst);
+            // Still full of clients draining their buckets: forget the longest idle one
+            if buckets.len() >= MAX_TRACKED_CLIENTS {
+                let idlest = buckets.iter().min_by_key(|(_, bucket)| bucket.refilled_at).map(|(key, _)| key.clone());
+                if let Some(key) = idlest {
+                    buckets.remove(&key);
+                }
+            }
+        }
+        let bucket = buckets.entry(client.clone()).or_insert(Bucket {
+            tokens: self.burst,
+            refilled_at: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.refilled_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let retry_after_secs = ((1.0 - bucket.tokens) / self.per_sec).ceil() as u64;
+            Err(AppError::RateLimited { retry_after_secs })
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        (bucket.tokens + elapsed * self.per_sec).min(self.burst)
+    }
+}
+
+// Schema extension charging every query, mutation and subscription to its client before
+// it is parsed. Operations without a `ClientKey` are not limited.
+pub struct RateLimit(pub RateLimiter);
+
+impl ExtensionFactory for RateLimit {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RateLimitExtension(self.0.clone()))
+    }
+}
+
+struct RateLimitExtension(RateLimiter);
+
+#[async_trait::async_trait]
+impl Extension for RateLimitExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let client = request
+            .data
+            .get(&TypeId::of::<ClientKey>())
+            .and_then(|data| data.downcast_ref::<ClientKey>())
+            .or_else(|| ctx.data_opt::<ClientKey>());
+        if let Some(client) = client {
+            self.0.check(client).map_err(|e| e.to_server_error())?;
+        }
+        next.run(ctx, request).await
+    }
+}
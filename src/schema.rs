@@ -1,23 +1,145 @@
-use crate::models::{ChainInfo, Event, EventFilterInput};
-use crate::indexer::SubstrateIndexerService;
+use crate::models::{
+    Account, Amount, BackfillStatus, Block, BlockConnection, ChainInfo, DecodedEvent, Event, Extrinsic, ExtrinsicConnection, ExtrinsicCursor, ExtrinsicFilterInput, EventConnection, EventCursor, EventDataFilterInput, EventFilterInput, EventNameCount, EventStats, PalletMetadata, RegisterChainInput, RotatedApiKey, SharedRuntimeMetadata,
+    Caller, DataFieldStats, GenericEventData, HealthStatus, Scope, TimeBucket, TimeBucketCount, TransferDirection, WorkerKind, WorkerStatus};
+use crate::indexer::{ReplayFrom, SubstrateIndexerService};
 use crate::errors::AppError;
-use crate::dataloader::{AppDataloader, ChainInfoLoaderKey, ChainInfoLoader};
+use crate::address::Address;
+use crate::auth::{Authenticator, ScopeGuard};
+use crate::health::HealthChecker;
+use crate::config::ChainConfig;
+use crate::ingest::Backfiller;
+use crate::workers::Workers;
+use crate::store::{BlockPage, EventPage, ExtrinsicPage};
+use crate::dataloader::{
+    BlockEventsLoader, BlockEventsLoaderKey, BlockLoader, BlockLoaderKey, ChainInfoLoaderKey, ChainInfoLoader,
+    ExtrinsicEventsLoader, ExtrinsicEventsLoaderKey, ExtrinsicLoader, ExtrinsicLoaderKey,
+};
+use async_graphql::connection::{query, CursorType, OpaqueCursor};
 use async_graphql::{
-    Context, Object, FieldResult, Subscription, ID, Schema, EmptyMutation, ComplexObject, dataloader::DataLoader
+    Context, ErrorExtensions, Object, FieldResult, Subscription, ID, Schema, ComplexObject, dataloader::DataLoader
 };
 use tokio_stream::Stream;
 use futures_util::stream::StreamExt;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
 use tracing::instrument;
 
+// Page size for `events` when neither `first` nor `last` is given
+const DEFAULT_EVENTS_PAGE_SIZE: usize = 50;
+const MAX_EVENTS_PAGE_SIZE: usize = 1000;
+const DEFAULT_BLOCKS_PAGE_SIZE: usize = 20;
+const MAX_BLOCKS_PAGE_SIZE: usize = 200;
+const DEFAULT_EXTRINSICS_PAGE_SIZE: usize = 50;
+const MAX_EXTRINSICS_PAGE_SIZE: usize = 1000;
+// Assumed list lengths when costing a query, the actual ones are unknown until resolved
+const ESTIMATED_EVENTS_PER_BLOCK: usize = 20;
+const ESTIMATED_EVENTS_PER_EXTRINSIC: usize = 4;
+// Cost of aggregating over every matching event, as much as reading the largest events page.
+// `eventStats` pays it once, each histogram and data field again for its own aggregation.
+const EVENT_STATS_SCAN_COMPLEXITY: usize = MAX_EVENTS_PAGE_SIZE;
+
+// Cost of a connection page: its size times the cost of one entry. Pages the resolver
+// refuses cost as much as the largest one it accepts.
+fn page_complexity(first: Option<i32>, last: Option<i32>, default_size: usize, max_size: usize, child_complexity: usize) -> usize {
+    let size = match first.or(last) {
+        Some(size) => usize::try_from(size).unwrap_or(max_size).min(max_size),
+        None => default_size,
+    };
+    size.max(1).saturating_mul(child_complexity)
+}
+
+// Format account ids are rendered in: the requested one, the chain's own otherwise
+async fn address_format(ctx: &Context<'_>, chain_id: &ID, requested: Option<u16>) -> FieldResult<u16> {
+    if let Some(format) = requested {
+        return Ok(Address::check_format(format)?);
+    }
+    let loader = ctx.data::<DataLoader<ChainInfoLoader>>()?;
+    match loader.load_one(ChainInfoLoaderKey(chain_id.clone())).await? {
+        Some(chain_info) => Ok(Address::check_format(chain_info.ssv58_prefix)?),
+        None => Err(AppError::NotFound(format!("ChainInfo not found for ID: {}", chain_id.as_str())).into()),
+    }
+}
+
+// Relay page of the chain's events matching `filter`, behind every events connection
+#[allow(clippy::too_many_arguments)]
+async fn event_connection(
+    indexer_service: &SubstrateIndexerService,
+    chain_id: &ID,
+    filter: Option<EventFilterInput>,
+    with_total_count: bool,
+    after: Option<String>,
+    before: Option<String>,
+    first: Option<i32>,
+    last: Option<i32>,
+) -> FieldResult<EventConnection> {
+    query(
+        after,
+        before,
+        first,
+        last,
+        |after: Option<OpaqueCursor<EventCursor>>, before: Option<OpaqueCursor<EventCursor>>, first, last| async move {
+            if first.is_some() && last.is_some() {
+                return Err(AppError::InvalidInput("Passing both `first` and `last` is not supported".to_string()).into());
+            }
+            let limit = first.or(last).unwrap_or(DEFAULT_EVENTS_PAGE_SIZE);
+            if limit > MAX_EVENTS_PAGE_SIZE {
+                return Err(AppError::InvalidInput(format!("Page size must not exceed {}", MAX_EVENTS_PAGE_SIZE)).into());
+            }
+            let page = EventPage {
+                after: after.map(|c| c.0),
+                before: before.map(|c| c.0),
+                limit,
+                from_oldest: last.is_some(),
+            };
+            indexer_service.list_events(chain_id, filter, page, with_total_count).await
+        },
+    )
+    .await
+}
+
+// Relay page of the chain's extrinsics matching `filter`, see `event_connection`
+async fn extrinsic_connection(
+    indexer_service: &SubstrateIndexerService,
+    chain_id: &ID,
+    filter: Option<ExtrinsicFilterInput>,
+    after: Option<String>,
+    before: Option<String>,
+    first: Option<i32>,
+    last: Option<i32>,
+) -> FieldResult<ExtrinsicConnection> {
+    query(
+        after,
+        before,
+        first,
+        last,
+        |after: Option<OpaqueCursor<ExtrinsicCursor>>, before: Option<OpaqueCursor<ExtrinsicCursor>>, first, last| async move {
+            if first.is_some() && last.is_some() {
+                return Err(AppError::InvalidInput("Passing both `first` and `last` is not supported".to_string()).into());
+            }
+            let limit = first.or(last).unwrap_or(DEFAULT_EXTRINSICS_PAGE_SIZE);
+            if limit > MAX_EXTRINSICS_PAGE_SIZE {
+                return Err(AppError::InvalidInput(format!("Page size must not exceed {}", MAX_EXTRINSICS_PAGE_SIZE)).into());
+            }
+            let page = ExtrinsicPage {
+                after: after.map(|c| c.0),
+                before: before.map(|c| c.0),
+                limit,
+                from_oldest: last.is_some(),
+            };
+            indexer_service.list_extrinsics(chain_id, filter, page).await
+        },
+    )
+    .await
+}
+
 // Define the Query root object
 pub struct QueryRoot;
 
 #[Object]
 impl QueryRoot {
-    #[instrument(skip(self))]
-    async fn health_check(&self) -> String {
-        "OK".to_string()
+    // Same report as /healthz and /readyz
+    #[instrument(name = "query.health_check", skip_all)]
+    async fn health_check<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<HealthStatus> {
+        Ok(ctx.data::<HealthChecker>()?.check().await)
     }
 
     #[instrument(skip(self))]
@@ -25,33 +147,339 @@ impl QueryRoot {
         message
     }
 
-    #[instrument(name = "query.chain_info", skip_all)]
+    // The caller this request is authenticated as
+    async fn viewer<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Caller> {
+        Ok(ctx.data::<Caller>()?.clone())
+    }
+
+    // Every chain in the registry, in configuration order
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.chains", skip_all)]
+    async fn chains<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+    ) -> FieldResult<Vec<ChainInfo>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.list_chains().await
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.chain_info", skip_all, fields(id))]
     async fn chain_info<'ctx>(
         &self,
         ctx: &Context<'ctx>,
+        id: ID,
     ) -> FieldResult<ChainInfo> {
         let indexer_service = ctx.data::<SubstrateIndexerService>()?;
-        indexer_service.get_chain_info().await
+        indexer_service.get_chain_info(&id).await
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.runtime_metadata", skip_all, fields(chain_id))]
+    async fn runtime_metadata<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+    ) -> FieldResult<Option<SharedRuntimeMetadata>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let metadata = indexer_service.runtime_metadata(&chain_id).await?;
+        Ok(metadata.map(SharedRuntimeMetadata))
+    }
+
+    // Progress of the chain's latest historical backfill, null if it was never backfilled
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.backfill_status", skip_all, fields(chain_id))]
+    async fn backfill_status<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+    ) -> FieldResult<Option<BackfillStatus>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.backfill_status(&chain_id).await
+    }
+
+    // Look up a block by exactly one of `number` or `hash`
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.block", skip_all, fields(chain_id, number, hash))]
+    async fn block<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+        number: Option<u64>,
+        hash: Option<String>,
+    ) -> FieldResult<Option<Block>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        match (number, hash) {
+            (Some(number), None) => indexer_service.get_block_by_number(&chain_id, number).await,
+            (None, Some(hash)) => indexer_service.get_block_by_hash(&chain_id, &hash).await,
+            _ => Err(AppError::InvalidInput("Pass exactly one of `number` or `hash`".to_string()).into()),
+        }
+    }
+
+    #[graphql(
+        guard = "ScopeGuard(Scope::Read)",
+        complexity = "page_complexity(first, last, DEFAULT_BLOCKS_PAGE_SIZE, MAX_BLOCKS_PAGE_SIZE, child_complexity)"
+    )]
+    #[instrument(name = "query.blocks", skip_all, fields(chain_id))]
+    #[allow(clippy::too_many_arguments)]
+    async fn blocks<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+        // Leave out blocks that may still be orphaned by a reorg
+        #[graphql(default)] finalized_only: bool,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> FieldResult<BlockConnection> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        query(
+            after,
+            before,
+            first,
+            last,
+            |after: Option<OpaqueCursor<u64>>, before: Option<OpaqueCursor<u64>>, first, last| async move {
+                if first.is_some() && last.is_some() {
+                    return Err(AppError::InvalidInput("Passing both `first` and `last` is not supported".to_string()).into());
+                }
+                let limit = first.or(last).unwrap_or(DEFAULT_BLOCKS_PAGE_SIZE);
+                if limit > MAX_BLOCKS_PAGE_SIZE {
+                    return Err(AppError::InvalidInput(format!("Page size must not exceed {}", MAX_BLOCKS_PAGE_SIZE)).into());
+                }
+                let page = BlockPage {
+                    after: after.map(|c| c.0),
+                    before: before.map(|c| c.0),
+                    limit,
+                    from_oldest: last.is_some(),
+                };
+                indexer_service.list_blocks(&chain_id, finalized_only, page).await
+            },
+        )
+        .await
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.extrinsic", skip_all, fields(chain_id, id))]
+    async fn extrinsic<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+        id: ID,
+    ) -> FieldResult<Option<Extrinsic>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.get_extrinsic(&chain_id, &id).await
     }
 
-    #[instrument(name = "query.event", skip_all, fields(id))]
+    #[graphql(
+        guard = "ScopeGuard(Scope::Read)",
+        complexity = "page_complexity(first, last, DEFAULT_EXTRINSICS_PAGE_SIZE, MAX_EXTRINSICS_PAGE_SIZE, child_complexity)"
+    )]
+    #[instrument(name = "query.extrinsics", skip_all, fields(chain_id, filter))]
+    #[allow(clippy::too_many_arguments)]
+    async fn extrinsics<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+        filter: Option<ExtrinsicFilterInput>,
+        // Shorthand for `filter: { finalized: true }`
+        #[graphql(default)] finalized_only: bool,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> FieldResult<ExtrinsicConnection> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let filter = if finalized_only {
+            Some(ExtrinsicFilterInput { finalized: Some(true), ..filter.unwrap_or_default() })
+        } else {
+            filter
+        };
+        extrinsic_connection(indexer_service, &chain_id, filter, after, before, first, last).await
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.event", skip_all, fields(chain_id, id))]
     async fn event<'ctx>(
         &self,
         ctx: &Context<'ctx>,
+        chain_id: ID,
         id: ID,
     ) -> FieldResult<Option<Event>> {
         let indexer_service = ctx.data::<SubstrateIndexerService>()?;
-        indexer_service.get_event_by_id(id).await
+        indexer_service.get_event_by_id(&chain_id, id).await
     }
 
-    #[instrument(name = "query.events", skip_all, fields(filter))]
+    #[graphql(
+        guard = "ScopeGuard(Scope::Read)",
+        complexity = "page_complexity(first, last, DEFAULT_EVENTS_PAGE_SIZE, MAX_EVENTS_PAGE_SIZE, child_complexity)"
+    )]
+    #[instrument(name = "query.events", skip_all, fields(chain_id, filter))]
+    #[allow(clippy::too_many_arguments)]
     async fn events<'ctx>(
         &self,
         ctx: &Context<'ctx>,
+        chain_id: ID,
+        filter: Option<EventFilterInput>,
+        // Shorthand for `filter: { data: [...] }`, combined with `filter` by AND
+        data_filter: Option<Vec<EventDataFilterInput>>,
+        // Shorthand for `filter: { finalized: true }`, events that can no longer be retracted
+        #[graphql(default)] finalized_only: bool,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> FieldResult<EventConnection> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let with_total_count = ctx.look_ahead().field("totalCount").exists();
+        let filter = EventFilterInput::with_data(filter, data_filter);
+        let filter = EventFilterInput::with_finalized_only(filter, finalized_only);
+        event_connection(indexer_service, &chain_id, filter, with_total_count, after, before, first, last).await
+    }
+
+    // Activity of `address` on the chain, whether or not it was ever seen
+    #[graphql(guard = "ScopeGuard(Scope::Read)")]
+    #[instrument(name = "query.account", skip_all, fields(chain_id, %address))]
+    async fn account<'ctx>(&self, ctx: &Context<'ctx>, chain_id: ID, address: Address) -> FieldResult<Account> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.account(&chain_id, address)
+    }
+
+    // Counts, histograms and sums over the events `events` would list for the same filter
+    #[graphql(
+        guard = "ScopeGuard(Scope::Read)",
+        complexity = "EVENT_STATS_SCAN_COMPLEXITY + child_complexity"
+    )]
+    #[instrument(name = "query.event_stats", skip_all, fields(chain_id, filter))]
+    async fn event_stats<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
         filter: Option<EventFilterInput>,
-    ) -> FieldResult<Vec<Event>> {
+        data_filter: Option<Vec<EventDataFilterInput>>,
+        #[graphql(default)] finalized_only: bool,
+    ) -> FieldResult<EventStats> {
         let indexer_service = ctx.data::<SubstrateIndexerService>()?;
-        indexer_service.list_events(filter).await
+        let filter = EventFilterInput::with_data(filter, data_filter);
+        let filter = EventFilterInput::with_finalized_only(filter, finalized_only);
+        indexer_service.event_stats(&chain_id, filter)
+    }
+}
+
+// Operator actions, every field requires the admin scope
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    // Add a chain to the registry and start feeding it. It is kept across restarts.
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.register_chain", skip_all, fields(id = %input.id.as_str()))]
+    async fn register_chain<'ctx>(&self, ctx: &Context<'ctx>, input: RegisterChainInput) -> FieldResult<ChainInfo> {
+        if input.id.is_empty() {
+            return Err(AppError::InvalidInput("Chain id must not be empty".to_string()).into());
+        }
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let workers = ctx.data::<Workers>()?;
+        let chain_info = indexer_service
+            .register_chain(ChainConfig {
+                id: input.id.to_string(),
+                name: input.name,
+                endpoint: input.endpoint,
+                replay_file: None,
+                backfill_from_block: input.backfill_from_block,
+                ss58_prefix: input.ss58_prefix,
+                token_symbol: input.token_symbol,
+                decimals: input.decimals,
+            })
+            .await?;
+        let kind = workers.default_kind();
+        workers.start(&input.id, kind).await?;
+        if let (Some(from_block), WorkerKind::Ingestion) = (input.backfill_from_block, kind) {
+            ctx.data::<Backfiller>()?.start(&input.id, from_block, None).await?;
+        }
+        Ok(chain_info)
+    }
+
+    // Stop feeding a chain registered at runtime and drop it from the registry. Its
+    // indexed data is kept.
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.remove_chain", skip_all, fields(chain_id = %chain_id.as_str()))]
+    async fn remove_chain<'ctx>(&self, ctx: &Context<'ctx>, chain_id: ID) -> FieldResult<ID> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.unregister_chain(&chain_id).await?;
+        ctx.data::<Backfiller>()?.cancel(&chain_id).await?;
+        ctx.data::<Workers>()?.retire(&chain_id).await;
+        Ok(chain_id)
+    }
+
+    // Follow the chain's node, resuming paused ingestion
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.start_ingestion", skip_all, fields(chain_id = %chain_id.as_str()))]
+    async fn start_ingestion<'ctx>(&self, ctx: &Context<'ctx>, chain_id: ID) -> FieldResult<WorkerStatus> {
+        Ok(ctx.data::<Workers>()?.start(&chain_id, WorkerKind::Ingestion).await?)
+    }
+
+    // Generate mock data for the chain, resuming a paused simulator
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.start_simulator", skip_all, fields(chain_id = %chain_id.as_str()))]
+    async fn start_simulator<'ctx>(&self, ctx: &Context<'ctx>, chain_id: ID) -> FieldResult<WorkerStatus> {
+        Ok(ctx.data::<Workers>()?.start(&chain_id, WorkerKind::Simulator).await?)
+    }
+
+    // Pause the chain's ingestion or simulator, start it again to resume
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.pause_worker", skip_all, fields(chain_id = %chain_id.as_str()))]
+    async fn pause_worker<'ctx>(&self, ctx: &Context<'ctx>, chain_id: ID) -> FieldResult<WorkerStatus> {
+        Ok(ctx.data::<Workers>()?.pause(&chain_id).await?)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.stop_worker", skip_all, fields(chain_id = %chain_id.as_str()))]
+    async fn stop_worker<'ctx>(&self, ctx: &Context<'ctx>, chain_id: ID) -> FieldResult<WorkerStatus> {
+        Ok(ctx.data::<Workers>()?.stop(&chain_id).await?)
+    }
+
+    // Backfill `fromBlock..=toBlock`, up to the finalized head when `toBlock` is omitted.
+    // Replaces the chain's previous backfill, see `backfillStatus`.
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.start_backfill", skip_all, fields(chain_id = %chain_id.as_str(), from_block, to_block))]
+    async fn start_backfill<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+        from_block: u64,
+        to_block: Option<u64>,
+    ) -> FieldResult<BackfillStatus> {
+        let worker = ctx.data::<Workers>()?.status(&chain_id).await;
+        if worker.is_some_and(|w| w.kind == WorkerKind::Simulator) {
+            return Err(AppError::InvalidInput(format!(
+                "Chain {} is fed by the simulator, stop it before backfilling",
+                chain_id.as_str()
+            ))
+            .into());
+        }
+        Ok(ctx.data::<Backfiller>()?.start(&chain_id, from_block, to_block).await?)
+    }
+
+    // Delete the chain's events older than `before`, returns how many were deleted
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.purge_events", skip_all, fields(chain_id = %chain_id.as_str(), %before))]
+    async fn purge_events<'ctx>(&self, ctx: &Context<'ctx>, chain_id: ID, before: DateTime<Utc>) -> FieldResult<u64> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        Ok(indexer_service.purge_events(&chain_id, before).await?)
+    }
+
+    // Issue a new API key named `name` and revoke the others of that name, configured ones
+    // included. The key is only shown once.
+    #[graphql(guard = "ScopeGuard(Scope::Admin)")]
+    #[instrument(name = "mutation.rotate_api_keys", skip_all, fields(%name))]
+    async fn rotate_api_keys<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        #[graphql(default_with = "\"admin\".to_string()")] name: String,
+        #[graphql(default_with = "vec![Scope::Admin]")] scopes: Vec<Scope>,
+    ) -> FieldResult<RotatedApiKey> {
+        Ok(ctx.data::<Authenticator>()?.rotate(name, scopes).await?)
     }
 }
 
@@ -60,23 +488,58 @@ pub struct SubscriptionRoot;
 
 #[Subscription]
 impl SubscriptionRoot {
-    #[instrument(name = "subscription.events", skip_all)]
+    // Filters are evaluated server-side, per subscriber, before anything is sent.
+    // `fromBlock` or `afterCursor` (an `Event.cursor`) first replays stored events, oldest
+    // first, then continues with live ones without gaps or duplicates. A SUBSCRIPTION_LAGGED
+    // error is sent when the subscriber fell behind; the missed events follow it.
+    // A CHAIN_REORG error with `fromBlock` is sent when blocks the subscriber got events from
+    // were orphaned: those events are void and the ones of the new branch follow. With
+    // `finalizedOnly` events are sent once their block is finalized and are never retracted.
+    #[graphql(guard = "ScopeGuard(Scope::Subscribe)")]
+    #[instrument(name = "subscription.events", skip_all, fields(chain_id, filter))]
+    #[allow(clippy::too_many_arguments)]
     async fn events<'ctx>(
         &self,
-        ctx: &Context<'ctx>
-    ) -> impl Stream<Item = Event> + 'ctx {
+        ctx: &Context<'ctx>,
+        chain_id: ID,
+        filter: Option<EventFilterInput>,
+        // Shorthand for `filter: { data: [...] }`, combined with `filter` by AND
+        data_filter: Option<Vec<EventDataFilterInput>>,
+        // Shorthand for `filter: { finalized: true }`
+        #[graphql(default)] finalized_only: bool,
+        from_block: Option<u64>,
+        after_cursor: Option<String>,
+    ) -> FieldResult<impl Stream<Item = FieldResult<Event>> + 'ctx> {
         let indexer_service = ctx.data_unchecked::<SubstrateIndexerService>().clone();
-        Box::pin(async_stream::stream! {
-            let mut inner_stream = indexer_service.watch_events().await;
+        let filter = EventFilterInput::with_data(filter, data_filter);
+        let filter = EventFilterInput::with_finalized_only(filter, finalized_only);
+        let replay = match (from_block, after_cursor) {
+            (Some(_), Some(_)) => {
+                return Err(AppError::InvalidInput("Pass either `fromBlock` or `afterCursor`, not both".to_string()).into())
+            }
+            (Some(0), None) => ReplayFrom::Beginning,
+            (Some(block), None) => ReplayFrom::After(EventCursor {
+                block_number: block - 1,
+                event_index: u32::MAX,
+            }),
+            (None, Some(cursor)) => {
+                let cursor = OpaqueCursor::<EventCursor>::decode_cursor(&cursor)
+                    .map_err(|e| AppError::InvalidInput(format!("Invalid cursor: {}", e)))?;
+                ReplayFrom::After(cursor.0)
+            }
+            (None, None) => ReplayFrom::Live,
+        };
+        let mut inner_stream = indexer_service.watch_events(chain_id, filter, replay).await?;
+        Ok(Box::pin(async_stream::stream! {
             while let Some(event) = inner_stream.next().await {
-                yield event;
+                yield event.map_err(|e| e.extend());
             }
-        })
+        }))
     }
 }
 
 // Schema type
-pub type AppSchema = Schema<QueryRoot, EmptyMutation, SubscriptionRoot>;
+pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 // Complex object implementations for relationships
 #[ComplexObject]
@@ -86,18 +549,331 @@ impl Event {
         let loader = ctx.data::<DataLoader<ChainInfoLoader>>()?;
         match loader.load_one(ChainInfoLoaderKey(self.chain_id.clone())).await? {
             Some(chain_info) => Ok(chain_info),
-            None => Err(AppError::NotFound(format!("ChainInfo not found for ID: {}", self.chain_id)).into()),
+            None => Err(AppError::NotFound(format!("ChainInfo not found for ID: {}", self.chain_id.as_str())).into()),
+        }
+    }
+
+    // `None` for events indexed before blocks were stored
+    #[instrument(name = "event.block", skip(self, ctx), fields(id, block_number))]
+    async fn block<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Option<Block>> {
+        let loader = ctx.data::<DataLoader<BlockLoader>>()?;
+        loader.load_one(BlockLoaderKey(self.chain_id.clone(), self.block_number)).await
+    }
+
+    // The extrinsic that emitted the event, `None` for initialization/finalization events
+    #[instrument(name = "event.extrinsic", skip(self, ctx), fields(id, extrinsic_id))]
+    async fn extrinsic<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Option<Extrinsic>> {
+        let Some(extrinsic_id) = &self.extrinsic_id else {
+            return Ok(None);
+        };
+        let loader = ctx.data::<DataLoader<ExtrinsicLoader>>()?;
+        loader.load_one(ExtrinsicLoaderKey(self.chain_id.clone(), ID::from(extrinsic_id.as_str()))).await
+    }
+
+    // Opaque position of the event, usable as `after`/`before` in `events` and as
+    // `afterCursor` to resume a subscription
+    async fn cursor(&self) -> String {
+        OpaqueCursor(EventCursor::from(self)).encode_cursor()
+    }
+
+    // The decoded payload, account ids as SS58 addresses in `ss58Format`, by default the chain's
+    async fn data<'ctx>(&self, ctx: &Context<'ctx>, ss58_format: Option<u16>) -> FieldResult<serde_json::Value> {
+        let format = address_format(ctx, &self.chain_id, ss58_format).await?;
+        let fields = ctx.data::<SubstrateIndexerService>()?.account_fields(&self.chain_id).await?;
+        Ok(fields.encode_event(&self.pallet_name, &self.event_name, &self.data, format))
+    }
+
+    // The payload as a typed object for well-known events, `GenericEventData` otherwise.
+    // Account ids are SS58 addresses in `ss58Format`, by default the chain's.
+    async fn decoded<'ctx>(&self, ctx: &Context<'ctx>, ss58_format: Option<u16>) -> FieldResult<DecodedEvent> {
+        let format = address_format(ctx, &self.chain_id, ss58_format).await?;
+        if let Some(decoded) = DecodedEvent::decode(&self.chain_id, &self.pallet_name, &self.event_name, &self.data, format) {
+            return Ok(decoded);
+        }
+        let fields = ctx.data::<SubstrateIndexerService>()?.account_fields(&self.chain_id).await?;
+        let data = fields.encode_event(&self.pallet_name, &self.event_name, &self.data, format);
+        Ok(DecodedEvent::GenericEventData(GenericEventData::new(&self.pallet_name, &self.event_name, data)))
+    }
+}
+
+#[ComplexObject]
+impl Block {
+    #[instrument(name = "block.chain", skip(self, ctx), fields(number))]
+    async fn chain<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<ChainInfo> {
+        let loader = ctx.data::<DataLoader<ChainInfoLoader>>()?;
+        match loader.load_one(ChainInfoLoaderKey(self.chain_id.clone())).await? {
+            Some(chain_info) => Ok(chain_info),
+            None => Err(AppError::NotFound(format!("ChainInfo not found for ID: {}", self.chain_id.as_str())).into()),
+        }
+    }
+
+    // `None` when the consensus digest does not identify the producer
+    async fn author<'ctx>(&self, ctx: &Context<'ctx>, ss58_format: Option<u16>) -> FieldResult<Option<Address>> {
+        let Some(author) = &self.author else {
+            return Ok(None);
+        };
+        let format = address_format(ctx, &self.chain_id, ss58_format).await?;
+        Ok(Address::parse(author).ok().map(|address| address.with_format(format)))
+    }
+
+    // Events emitted in this block, in order
+    #[graphql(complexity = "ESTIMATED_EVENTS_PER_BLOCK * child_complexity")]
+    #[instrument(name = "block.events", skip(self, ctx), fields(number))]
+    async fn events<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Vec<Event>> {
+        let loader = ctx.data::<DataLoader<BlockEventsLoader>>()?;
+        let events = loader.load_one(BlockEventsLoaderKey(self.chain_id.clone(), self.number)).await?;
+        Ok(events.unwrap_or_default())
+    }
+}
+
+#[ComplexObject]
+impl BackfillStatus {
+    async fn chain<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<ChainInfo> {
+        let loader = ctx.data::<DataLoader<ChainInfoLoader>>()?;
+        match loader.load_one(ChainInfoLoaderKey(self.chain_id.clone())).await? {
+            Some(chain_info) => Ok(chain_info),
+            None => Err(AppError::NotFound(format!("ChainInfo not found for ID: {}", self.chain_id.as_str())).into()),
+        }
+    }
+
+    async fn total_blocks(&self) -> u64 {
+        self.to_block + 1 - self.from_block
+    }
+
+    async fn indexed_blocks(&self) -> u64 {
+        self.next_block - self.from_block
+    }
+
+    // Fraction of the range indexed so far, from 0 to 1
+    async fn progress(&self) -> f64 {
+        (self.next_block - self.from_block) as f64 / (self.to_block + 1 - self.from_block) as f64
+    }
+}
+
+#[ComplexObject]
+impl Extrinsic {
+    #[instrument(name = "extrinsic.block", skip(self, ctx), fields(id))]
+    async fn block<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Option<Block>> {
+        let loader = ctx.data::<DataLoader<BlockLoader>>()?;
+        loader.load_one(BlockLoaderKey(self.chain_id.clone(), self.block_number)).await
+    }
+
+    // Events emitted while applying this extrinsic, in order
+    #[graphql(complexity = "ESTIMATED_EVENTS_PER_EXTRINSIC * child_complexity")]
+    #[instrument(name = "extrinsic.events", skip(self, ctx), fields(id))]
+    async fn events<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Vec<Event>> {
+        let loader = ctx.data::<DataLoader<ExtrinsicEventsLoader>>()?;
+        let events = loader.load_one(ExtrinsicEventsLoaderKey(self.chain_id.clone(), self.id.clone())).await?;
+        Ok(events.unwrap_or_default())
+    }
+
+    // Opaque position of the extrinsic, usable as `after`/`before` in `extrinsics`
+    async fn cursor(&self) -> String {
+        OpaqueCursor(ExtrinsicCursor::from(self)).encode_cursor()
+    }
+
+    // `None` for unsigned extrinsics and for signers that are not a plain account id
+    async fn signer<'ctx>(&self, ctx: &Context<'ctx>, ss58_format: Option<u16>) -> FieldResult<Option<Address>> {
+        let Some(signer) = &self.signer else {
+            return Ok(None);
+        };
+        let format = address_format(ctx, &self.chain_id, ss58_format).await?;
+        Ok(Address::parse(signer).ok().map(|address| address.with_format(format)))
+    }
+
+    // `None` for unsigned extrinsics
+    async fn tip(&self) -> Option<Amount> {
+        self.amount(self.tip.as_deref())
+    }
+
+    // `None` until the fee is known, and for unsigned extrinsics
+    async fn fee(&self) -> Option<Amount> {
+        self.amount(self.fee.as_deref())
+    }
+
+    // Call arguments, account ids as SS58 addresses in `ss58Format`, by default the chain's
+    async fn args<'ctx>(&self, ctx: &Context<'ctx>, ss58_format: Option<u16>) -> FieldResult<serde_json::Value> {
+        let format = address_format(ctx, &self.chain_id, ss58_format).await?;
+        let fields = ctx.data::<SubstrateIndexerService>()?.account_fields(&self.chain_id).await?;
+        Ok(fields.encode_call(&self.pallet_name, &self.call_name, &self.args, format))
+    }
+}
+
+#[ComplexObject]
+impl Amount {
+    // The value in whole tokens with `symbol` appended, e.g. "1.5 DOT". Both default to
+    // the chain's `decimals` and `tokenSymbol`, pass an empty symbol to leave it out.
+    async fn formatted<'ctx>(&self, ctx: &Context<'ctx>, decimals: Option<u8>, symbol: Option<String>) -> FieldResult<String> {
+        if let (Some(decimals), Some(symbol)) = (decimals, &symbol) {
+            return Ok(self.value.format(decimals, symbol));
         }
+        let loader = ctx.data::<DataLoader<ChainInfoLoader>>()?;
+        let Some(chain_info) = loader.load_one(ChainInfoLoaderKey(self.chain_id.clone())).await? else {
+            return Err(AppError::NotFound(format!("ChainInfo not found for ID: {}", self.chain_id.as_str())).into());
+        };
+        let symbol = symbol.unwrap_or(chain_info.token_symbol);
+        Ok(self.value.format(decimals.unwrap_or(chain_info.decimals), &symbol))
+    }
+}
+
+#[ComplexObject]
+impl Account {
+    // The account id as an SS58 address in `ss58Format`, by default the chain's
+    async fn address<'ctx>(&self, ctx: &Context<'ctx>, ss58_format: Option<u16>) -> FieldResult<Address> {
+        let format = address_format(ctx, &self.chain_id, ss58_format).await?;
+        Ok(self.address.with_format(format))
+    }
+
+    // Events whose data holds the account id, newest first
+    #[graphql(complexity = "page_complexity(first, last, DEFAULT_EVENTS_PAGE_SIZE, MAX_EVENTS_PAGE_SIZE, child_complexity)")]
+    #[instrument(name = "account.events", skip_all, fields(chain_id = %self.chain_id.as_str(), address = %self.address))]
+    async fn events<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        filter: Option<EventFilterInput>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> FieldResult<EventConnection> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let with_total_count = ctx.look_ahead().field("totalCount").exists();
+        let filter = EventFilterInput::with_account(filter, self.address);
+        event_connection(indexer_service, &self.chain_id, Some(filter), with_total_count, after, before, first, last).await
+    }
+
+    // `Balances.Transfer` events the account sent or received, newest first
+    #[graphql(complexity = "page_complexity(first, last, DEFAULT_EVENTS_PAGE_SIZE, MAX_EVENTS_PAGE_SIZE, child_complexity)")]
+    #[instrument(name = "account.transfers", skip_all, fields(chain_id = %self.chain_id.as_str(), address = %self.address, ?direction))]
+    async fn transfers<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        #[graphql(default)] direction: TransferDirection,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> FieldResult<EventConnection> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let with_total_count = ctx.look_ahead().field("totalCount").exists();
+        let filter = EventFilterInput::transfers(self.address, direction);
+        event_connection(indexer_service, &self.chain_id, Some(filter), with_total_count, after, before, first, last).await
+    }
+
+    // Extrinsics the account signed, newest first. `filter.signerEq` is ignored.
+    #[graphql(complexity = "page_complexity(first, last, DEFAULT_EXTRINSICS_PAGE_SIZE, MAX_EXTRINSICS_PAGE_SIZE, child_complexity)")]
+    #[instrument(name = "account.extrinsics_signed", skip_all, fields(chain_id = %self.chain_id.as_str(), address = %self.address))]
+    async fn extrinsics_signed<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        filter: Option<ExtrinsicFilterInput>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> FieldResult<ExtrinsicConnection> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let filter = ExtrinsicFilterInput { signer_eq: Some(self.address), ..filter.unwrap_or_default() };
+        extrinsic_connection(indexer_service, &self.chain_id, Some(filter), after, before, first, last).await
+    }
+
+    // Block the account first showed up in, null if it never did or the block is not stored
+    #[instrument(name = "account.first_seen_block", skip_all, fields(chain_id = %self.chain_id.as_str(), address = %self.address))]
+    async fn first_seen_block<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Option<Block>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let Some((first, _)) = indexer_service.account_activity(&self.chain_id, self.address).await? else {
+            return Ok(None);
+        };
+        let loader = ctx.data::<DataLoader<BlockLoader>>()?;
+        loader.load_one(BlockLoaderKey(self.chain_id.clone(), first)).await
+    }
+
+    // Block the account last showed up in, see `firstSeenBlock`
+    #[instrument(name = "account.last_seen_block", skip_all, fields(chain_id = %self.chain_id.as_str(), address = %self.address))]
+    async fn last_seen_block<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Option<Block>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let Some((_, last)) = indexer_service.account_activity(&self.chain_id, self.address).await? else {
+            return Ok(None);
+        };
+        let loader = ctx.data::<DataLoader<BlockLoader>>()?;
+        loader.load_one(BlockLoaderKey(self.chain_id.clone(), last)).await
+    }
+}
+
+#[ComplexObject]
+impl EventStats {
+    // Number of matching events
+    async fn count<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<u64> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.event_stats_count(&self.chain_id, self.filter.as_ref()).await
+    }
+
+    // Matching events per pallet and event name, most frequent first
+    async fn by_name<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Vec<EventNameCount>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.event_stats_by_name(&self.chain_id, self.filter.as_ref()).await
+    }
+
+    // Matching events per `bucket` of time, oldest first. Buckets without events are left out.
+    #[graphql(complexity = "EVENT_STATS_SCAN_COMPLEXITY + child_complexity")]
+    async fn histogram<'ctx>(&self, ctx: &Context<'ctx>, bucket: TimeBucket) -> FieldResult<Vec<TimeBucketCount>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.event_stats_histogram(&self.chain_id, self.filter.as_ref(), bucket).await
+    }
+
+    // Sum, min and max of the integers at `path` in the matching events' data, e.g. "amount".
+    // Alias the field to aggregate several paths in one query.
+    #[graphql(complexity = "EVENT_STATS_SCAN_COMPLEXITY + child_complexity")]
+    async fn data<'ctx>(&self, ctx: &Context<'ctx>, path: String) -> FieldResult<DataFieldStats> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.event_stats_data(&self.chain_id, self.filter.as_ref(), &path).await
     }
 }
 
 #[ComplexObject]
 impl ChainInfo {
-    #[instrument(name = "chain_info.current_block_height", skip(self, _ctx), fields(id, name))]
-    async fn current_block_height<'ctx>(&self, _ctx: &Context<'ctx>) -> FieldResult<u64> {
-        let store_lock = crate::indexer::MOCK_EVENT_STORE.lock()
-            .map_err(|e| AppError::Internal(format!("Failed to lock event store for block height: {}", e)))?;
-        let max_block = store_lock.events.values().map(|e| e.block_number).max().unwrap_or(0);
-        Ok(max_block)
-    }
-} 
\ No newline at end of file
+    #[instrument(name = "chain_info.current_block_height", skip(self, ctx), fields(id, name))]
+    async fn current_block_height<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<u64> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.current_block_height(&self.id).await
+    }
+
+    // Highest block that can no longer be reorganized away
+    #[instrument(name = "chain_info.finalized_block_height", skip(self, ctx), fields(id, name))]
+    async fn finalized_block_height<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<u64> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        indexer_service.finalized_block_height(&self.id).await
+    }
+
+    // What feeds the chain, null when nothing does
+    async fn worker<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Option<WorkerStatus>> {
+        Ok(ctx.data::<Workers>()?.status(&self.id).await)
+    }
+
+    #[instrument(name = "chain_info.runtime_metadata", skip(self, ctx), fields(id, name))]
+    async fn runtime_metadata<'ctx>(&self, ctx: &Context<'ctx>) -> FieldResult<Option<SharedRuntimeMetadata>> {
+        let indexer_service = ctx.data::<SubstrateIndexerService>()?;
+        let metadata = indexer_service.runtime_metadata(&self.id).await?;
+        Ok(metadata.map(SharedRuntimeMetadata))
+    }
+}
+
+#[Object(name = "RuntimeMetadata")]
+impl SharedRuntimeMetadata {
+    async fn spec_version(&self) -> u32 {
+        self.0.spec_version
+    }
+
+    async fn metadata_version(&self) -> u32 {
+        self.0.metadata_version
+    }
+
+    async fn pallets(&self) -> &[PalletMetadata] {
+        &self.0.pallets
+    }
+
+    // Look up a single pallet by name, e.g. `pallet(name: "Balances")`
+    async fn pallet(&self, name: String) -> Option<&PalletMetadata> {
+        self.0.pallets.iter().find(|p| p.name == name)
+    }
+}
\ No newline at end of file
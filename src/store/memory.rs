@@ -0,0 +1,538 @@
+use super::{AccountStore, ApiKeyStore, BackfillStore, BlockPage, BlockStore, ChainStore, EventPage, EventStore, ExtrinsicPage, ExtrinsicStore, IndexedBlock, RawMetadata, StoredApiKey};
+use crate::address::{mentions_account, ALICE, BOB};
+use crate::config::ChainConfig;
+use crate::errors::{AppError, Result};
+use crate::models::{
+    lookup_data_path, BackfillStatus, Block, ChainInfo, DataFieldStats, Event, EventCursor, EventFilterInput, EventNameCount, Extrinsic,
+    ExtrinsicCursor, ExtrinsicFilterInput, PathSegment, TimeBucketCount,
+};
+use async_graphql::ID;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+#[derive(Default)]
+struct Inner {
+    events: HashMap<ID, Event>,
+    chain_infos: HashMap<ID, ChainInfo>,
+    // Only the latest runtime version is kept
+    runtime_metadata: HashMap<ID, RawMetadata>,
+    blocks: HashMap<ID, BTreeMap<u64, Block>>,
+    extrinsics: HashMap<ID, BTreeMap<ExtrinsicCursor, Extrinsic>>,
+    backfills: HashMap<ID, BackfillStatus>,
+    registered_chains: Vec<ChainConfig>,
+    api_keys: Vec<StoredApiKey>,
+}
+
+// Volatile store backed by plain HashMaps, everything is lost on restart.
+#[derive(Default)]
+pub struct MemoryStore {
+    inner: Mutex<Inner>,
+}
+
+impl MemoryStore {
+    // Store pre-populated with a handful of blocks on each chain, one transfer in each.
+    pub fn with_mock_data(chain_ids: &[ID]) -> Self {
+        let mut events = HashMap::new();
+        let mut blocks = HashMap::new();
+        let mut extrinsics = HashMap::new();
+        for chain_id in chain_ids {
+            let chain_blocks: &mut BTreeMap<u64, Block> = blocks.entry(chain_id.clone()).or_default();
+            let chain_extrinsics: &mut BTreeMap<ExtrinsicCursor, Extrinsic> = extrinsics.entry(chain_id.clone()).or_default();
+            let mut parent_hash = format!("0x{}", "00".repeat(32));
+            for i in 0..5u64 {
+                let timestamp = Utc::now() - ChronoDuration::seconds((5 - i) as i64 * 10);
+                let block = Block::mock(chain_id, 10000 + i, parent_hash, timestamp);
+                parent_hash = block.hash.clone();
+                chain_blocks.insert(block.number, block);
+
+                let amount = (100 + i as u128) * 1_000_000_000_000u128;
+                let extrinsic = Extrinsic::mock(
+                    chain_id,
+                    10000 + i,
+                    0,
+                    Some(ALICE),
+                    ("Balances", "transfer_keep_alive"),
+                    json!({ "dest": BOB, "value": amount }),
+                );
+                let extrinsic_id = extrinsic.id.to_string();
+                chain_extrinsics.insert(ExtrinsicCursor::from(&extrinsic), extrinsic);
+
+                let event_id = ID::from(Uuid::new_v4().to_string());
+                let event = Event {
+                    id: event_id.clone(),
+                    block_number: 10000 + i,
+                    event_index: 0,
+                    extrinsic_id: Some(extrinsic_id),
+                    timestamp,
+                    pallet_name: "Balances".to_string(),
+                    event_name: "Transfer".to_string(),
+                    data: json!({ "from": ALICE, "to": BOB, "amount": amount }),
+                    finalized: true,
+                    chain_id: chain_id.clone(),
+                };
+                events.insert(event_id, event);
+            }
+        }
+
+        Self {
+            inner: Mutex::new(Inner {
+                events,
+                blocks,
+                extrinsics,
+                ..Inner::default()
+            }),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Inner>> {
+        self.inner
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock event store: {}", e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl EventStore for MemoryStore {
+    async fn get_event_by_id(&self, id: &ID) -> Result<Option<Event>> {
+        Ok(self.lock()?.events.get(id).cloned())
+    }
+
+    async fn list_events(&self, chain_id: &ID, filter: Option<&EventFilterInput>, page: &EventPage) -> Result<Vec<Event>> {
+        let store = self.lock()?;
+        let mut events: Vec<&Event> = store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id)
+            .filter(|e| filter.is_none_or(|f| f.matches(e)))
+            .filter(|e| page.contains(EventCursor::from(*e)))
+            .collect();
+        events.sort_by_key(|e| std::cmp::Reverse(EventCursor::from(*e))); // Sort by newest first
+        let skip = if page.from_oldest { events.len().saturating_sub(page.limit) } else { 0 };
+        Ok(events.into_iter().skip(skip).take(page.limit).cloned().collect())
+    }
+
+    async fn count_events(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> Result<u64> {
+        let store = self.lock()?;
+        let count = store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id && filter.is_none_or(|f| f.matches(e)))
+            .count();
+        Ok(count as u64)
+    }
+
+    async fn count_events_by_name(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> Result<Vec<EventNameCount>> {
+        let store = self.lock()?;
+        let mut counts: HashMap<(&str, &str), u64> = HashMap::new();
+        for e in store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id && filter.is_none_or(|f| f.matches(e)))
+        {
+            *counts.entry((e.pallet_name.as_str(), e.event_name.as_str())).or_default() += 1;
+        }
+        let mut counts: Vec<EventNameCount> = counts
+            .into_iter()
+            .map(|((pallet_name, event_name), count)| EventNameCount {
+                pallet_name: pallet_name.to_string(),
+                event_name: event_name.to_string(),
+                count,
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| (&a.pallet_name, &a.event_name).cmp(&(&b.pallet_name, &b.event_name)))
+        });
+        Ok(counts)
+    }
+
+    async fn count_events_by_time(
+        &self,
+        chain_id: &ID,
+        filter: Option<&EventFilterInput>,
+        bucket_ms: i64,
+    ) -> Result<Vec<TimeBucketCount>> {
+        let store = self.lock()?;
+        let mut counts: BTreeMap<i64, u64> = BTreeMap::new();
+        for e in store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id && filter.is_none_or(|f| f.matches(e)))
+        {
+            *counts.entry(e.timestamp.timestamp_millis().div_euclid(bucket_ms) * bucket_ms).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(start, count)| {
+                let start = DateTime::from_timestamp_millis(start)
+                    .ok_or_else(|| AppError::Internal(format!("Bucket start out of range: {}", start)))?;
+                Ok(TimeBucketCount { start, count })
+            })
+            .collect()
+    }
+
+    async fn event_data_stats(
+        &self,
+        chain_id: &ID,
+        filter: Option<&EventFilterInput>,
+        path: &[PathSegment<'_>],
+    ) -> Result<DataFieldStats> {
+        let store = self.lock()?;
+        let mut stats = DataFieldStats::default();
+        for value in store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id && filter.is_none_or(|f| f.matches(e)))
+            .filter_map(|e| lookup_data_path(&e.data, path))
+        {
+            stats.add(value);
+        }
+        Ok(stats)
+    }
+
+    async fn list_events_in_blocks(&self, chain_id: &ID, block_numbers: &[u64]) -> Result<Vec<Event>> {
+        let store = self.lock()?;
+        let mut events: Vec<Event> = store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id && block_numbers.contains(&e.block_number))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| EventCursor::from(e));
+        Ok(events)
+    }
+
+    async fn list_events_for_extrinsics(&self, chain_id: &ID, extrinsic_ids: &[ID]) -> Result<Vec<Event>> {
+        let store = self.lock()?;
+        let mut events: Vec<Event> = store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id)
+            .filter(|e| e.extrinsic_id.as_ref().is_some_and(|id| extrinsic_ids.iter().any(|x| x.as_str() == id)))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| EventCursor::from(e));
+        Ok(events)
+    }
+
+    async fn insert_event(&self, event: Event) -> Result<()> {
+        self.lock()?.events.insert(event.id.clone(), event);
+        Ok(())
+    }
+
+    async fn delete_events_before(&self, chain_id: &ID, before: DateTime<Utc>) -> Result<u64> {
+        let mut store = self.lock()?;
+        let count = store.events.len();
+        store.events.retain(|_, e| &e.chain_id != chain_id || e.timestamp >= before);
+        Ok((count - store.events.len()) as u64)
+    }
+
+    async fn max_block_number(&self, chain_id: &ID) -> Result<Option<u64>> {
+        let store = self.lock()?;
+        Ok(store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id)
+            .map(|e| e.block_number)
+            .max())
+    }
+}
+
+#[async_trait::async_trait]
+impl ChainStore for MemoryStore {
+    async fn get_chain_info(&self, id: &ID) -> Result<Option<ChainInfo>> {
+        Ok(self.lock()?.chain_infos.get(id).cloned())
+    }
+
+    async fn get_chain_infos_batch(&self, ids: &[ID]) -> Result<HashMap<ID, ChainInfo>> {
+        let store = self.lock()?;
+        Ok(ids
+            .iter()
+            .filter_map(|id| store.chain_infos.get(id).map(|info| (id.clone(), info.clone())))
+            .collect())
+    }
+
+    async fn upsert_chain_info(&self, chain_info: ChainInfo) -> Result<()> {
+        self.lock()?.chain_infos.insert(chain_info.id.clone(), chain_info);
+        Ok(())
+    }
+
+    async fn save_runtime_metadata(&self, chain_id: &ID, metadata: RawMetadata) -> Result<()> {
+        let mut store = self.lock()?;
+        let is_newer = store
+            .runtime_metadata
+            .get(chain_id)
+            .is_none_or(|current| current.spec_version <= metadata.spec_version);
+        if is_newer {
+            store.runtime_metadata.insert(chain_id.clone(), metadata);
+        }
+        Ok(())
+    }
+
+    async fn get_runtime_metadata(&self, chain_id: &ID) -> Result<Option<RawMetadata>> {
+        Ok(self.lock()?.runtime_metadata.get(chain_id).cloned())
+    }
+
+    async fn list_registered_chains(&self) -> Result<Vec<ChainConfig>> {
+        Ok(self.lock()?.registered_chains.clone())
+    }
+
+    async fn save_registered_chain(&self, chain: ChainConfig) -> Result<()> {
+        let mut store = self.lock()?;
+        match store.registered_chains.iter_mut().find(|c| c.id == chain.id) {
+            Some(current) => *current = chain,
+            None => store.registered_chains.push(chain),
+        }
+        Ok(())
+    }
+
+    async fn delete_registered_chain(&self, id: &ID) -> Result<()> {
+        self.lock()?.registered_chains.retain(|c| c.id != id.as_str());
+        Ok(())
+    }
+
+    // Only fails when the lock is poisoned
+    async fn ping(&self) -> Result<()> {
+        self.lock().map(|_| ())
+    }
+}
+
+#[async_trait::async_trait]
+impl BlockStore for MemoryStore {
+    async fn get_block_by_number(&self, chain_id: &ID, number: u64) -> Result<Option<Block>> {
+        Ok(self.lock()?.blocks.get(chain_id).and_then(|b| b.get(&number)).cloned())
+    }
+
+    async fn get_block_by_hash(&self, chain_id: &ID, hash: &str) -> Result<Option<Block>> {
+        let store = self.lock()?;
+        Ok(store
+            .blocks
+            .get(chain_id)
+            .and_then(|blocks| blocks.values().find(|b| b.hash == hash))
+            .cloned())
+    }
+
+    async fn get_blocks_batch(&self, chain_id: &ID, numbers: &[u64]) -> Result<HashMap<u64, Block>> {
+        let store = self.lock()?;
+        let Some(blocks) = store.blocks.get(chain_id) else {
+            return Ok(HashMap::new());
+        };
+        Ok(numbers
+            .iter()
+            .filter_map(|number| blocks.get(number).map(|block| (*number, block.clone())))
+            .collect())
+    }
+
+    async fn list_blocks(&self, chain_id: &ID, finalized_only: bool, page: &BlockPage) -> Result<Vec<Block>> {
+        let store = self.lock()?;
+        let Some(blocks) = store.blocks.get(chain_id) else {
+            return Ok(Vec::new());
+        };
+        let in_range = blocks
+            .values()
+            .filter(|b| page.contains(b.number) && (b.finalized || !finalized_only));
+        if page.from_oldest {
+            let mut oldest: Vec<Block> = in_range.take(page.limit).cloned().collect();
+            oldest.reverse(); // Sort by newest first
+            Ok(oldest)
+        } else {
+            Ok(in_range.rev().take(page.limit).cloned().collect())
+        }
+    }
+
+    async fn upsert_block(&self, block: Block) -> Result<()> {
+        self.lock()?
+            .blocks
+            .entry(block.chain_id.clone())
+            .or_default()
+            .insert(block.number, block);
+        Ok(())
+    }
+
+    async fn indexed_block_height(&self, chain_id: &ID) -> Result<Option<u64>> {
+        let store = self.lock()?;
+        Ok(store
+            .blocks
+            .get(chain_id)
+            .and_then(|blocks| blocks.keys().next_back().copied()))
+    }
+
+    async fn finalized_block_height(&self, chain_id: &ID) -> Result<Option<u64>> {
+        let store = self.lock()?;
+        Ok(store
+            .blocks
+            .get(chain_id)
+            .and_then(|blocks| blocks.values().rev().find(|b| b.finalized))
+            .map(|b| b.number))
+    }
+
+    async fn finalize_blocks(&self, chain_id: &ID, number: u64) -> Result<Vec<u64>> {
+        let mut store = self.lock()?;
+        let store = &mut *store;
+        let Some(blocks) = store.blocks.get_mut(chain_id) else {
+            return Ok(Vec::new());
+        };
+        let mut finalized = Vec::new();
+        for block in blocks.range_mut(..=number).map(|(_, b)| b).filter(|b| !b.finalized) {
+            block.finalized = true;
+            finalized.push(block.number);
+        }
+        for event in store.events.values_mut() {
+            if &event.chain_id == chain_id && event.block_number <= number {
+                event.finalized = true;
+            }
+        }
+        if let Some(extrinsics) = store.extrinsics.get_mut(chain_id) {
+            for extrinsic in extrinsics.values_mut().filter(|x| x.block_number <= number) {
+                extrinsic.finalized = true;
+            }
+        }
+        Ok(finalized)
+    }
+
+    async fn rollback_blocks(&self, chain_id: &ID, number: u64) -> Result<Vec<Block>> {
+        let mut store = self.lock()?;
+        store
+            .events
+            .retain(|_, e| &e.chain_id != chain_id || e.block_number < number);
+        if let Some(extrinsics) = store.extrinsics.get_mut(chain_id) {
+            extrinsics.retain(|position, _| position.block_number < number);
+        }
+        let removed = match store.blocks.get_mut(chain_id) {
+            Some(blocks) => blocks.split_off(&number).into_values().collect(),
+            None => Vec::new(),
+        };
+        Ok(removed)
+    }
+}
+
+#[async_trait::async_trait]
+impl ExtrinsicStore for MemoryStore {
+    async fn get_extrinsic(&self, chain_id: &ID, id: &ID) -> Result<Option<Extrinsic>> {
+        let store = self.lock()?;
+        Ok(store
+            .extrinsics
+            .get(chain_id)
+            .and_then(|extrinsics| extrinsics.values().find(|x| &x.id == id))
+            .cloned())
+    }
+
+    async fn get_extrinsics_batch(&self, chain_id: &ID, ids: &[ID]) -> Result<HashMap<ID, Extrinsic>> {
+        let store = self.lock()?;
+        let Some(extrinsics) = store.extrinsics.get(chain_id) else {
+            return Ok(HashMap::new());
+        };
+        Ok(extrinsics
+            .values()
+            .filter(|x| ids.contains(&x.id))
+            .map(|x| (x.id.clone(), x.clone()))
+            .collect())
+    }
+
+    async fn list_extrinsics(
+        &self,
+        chain_id: &ID,
+        filter: Option<&ExtrinsicFilterInput>,
+        page: &ExtrinsicPage,
+    ) -> Result<Vec<Extrinsic>> {
+        let store = self.lock()?;
+        let Some(extrinsics) = store.extrinsics.get(chain_id) else {
+            return Ok(Vec::new());
+        };
+        let in_range = extrinsics
+            .iter()
+            .filter(|(position, _)| page.contains(**position))
+            .map(|(_, x)| x)
+            .filter(|x| filter.is_none_or(|f| f.matches(x)));
+        if page.from_oldest {
+            let mut oldest: Vec<Extrinsic> = in_range.take(page.limit).cloned().collect();
+            oldest.reverse(); // Sort by newest first
+            Ok(oldest)
+        } else {
+            Ok(in_range.rev().take(page.limit).cloned().collect())
+        }
+    }
+
+    async fn insert_extrinsic(&self, extrinsic: Extrinsic) -> Result<()> {
+        self.lock()?
+            .extrinsics
+            .entry(extrinsic.chain_id.clone())
+            .or_default()
+            .insert(ExtrinsicCursor::from(&extrinsic), extrinsic);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl AccountStore for MemoryStore {
+    async fn account_activity(&self, chain_id: &ID, account: &str) -> Result<Option<(u64, u64)>> {
+        let store = self.lock()?;
+        let in_events = store
+            .events
+            .values()
+            .filter(|e| &e.chain_id == chain_id && mentions_account(&e.data, account))
+            .map(|e| e.block_number);
+        let signed = store
+            .extrinsics
+            .get(chain_id)
+            .into_iter()
+            .flat_map(|extrinsics| extrinsics.values())
+            .filter(|x| x.signer.as_deref() == Some(account))
+            .map(|x| x.block_number);
+        Ok(in_events.chain(signed).fold(None, |range, number| match range {
+            Some((first, last)) => Some((number.min(first), number.max(last))),
+            None => Some((number, number)),
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl BackfillStore for MemoryStore {
+    async fn get_backfill(&self, chain_id: &ID) -> Result<Option<BackfillStatus>> {
+        Ok(self.lock()?.backfills.get(chain_id).cloned())
+    }
+
+    async fn save_backfill(&self, status: BackfillStatus) -> Result<()> {
+        self.lock()?.backfills.insert(status.chain_id.clone(), status);
+        Ok(())
+    }
+
+    async fn save_backfill_batch(&self, blocks: Vec<IndexedBlock>, status: BackfillStatus) -> Result<()> {
+        let mut store = self.lock()?;
+        for indexed in blocks {
+            let chain_id = indexed.block.chain_id.clone();
+            store.blocks.entry(chain_id.clone()).or_default().insert(indexed.block.number, indexed.block);
+            let extrinsics = store.extrinsics.entry(chain_id).or_default();
+            extrinsics.extend(indexed.extrinsics.into_iter().map(|e| (ExtrinsicCursor::from(&e), e)));
+            store.events.extend(indexed.events.into_iter().map(|e| (e.id.clone(), e)));
+        }
+        store.backfills.insert(status.chain_id.clone(), status);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for MemoryStore {
+    async fn list_api_keys(&self) -> Result<Vec<StoredApiKey>> {
+        Ok(self.lock()?.api_keys.clone())
+    }
+
+    async fn get_api_key_by_hash(&self, key_hash: &str) -> Result<Option<StoredApiKey>> {
+        Ok(self.lock()?.api_keys.iter().find(|k| k.key_hash == key_hash).cloned())
+    }
+
+    async fn has_api_key_named(&self, name: &str) -> Result<bool> {
+        Ok(self.lock()?.api_keys.iter().any(|k| k.name == name))
+    }
+
+    async fn rotate_api_key(&self, key: StoredApiKey) -> Result<()> {
+        let mut inner = self.lock()?;
+        inner.api_keys.retain(|k| k.name != key.name);
+        inner.api_keys.push(key);
+        Ok(())
+    }
+}
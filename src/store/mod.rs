@@ -0,0 +1,405 @@
+use crate::config::{ChainConfig, StorageBackend, StorageConfig};
+use crate::errors::Result;
+use crate::models::{
+    BackfillStatus, Block, ChainInfo, DataFieldStats, Event, EventCursor, EventFilterInput, EventNameCount, Extrinsic, ExtrinsicCursor,
+    ExtrinsicFilterInput, PathSegment, Scope, TimeBucketCount,
+};
+use async_graphql::ID;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+mod memory;
+mod sqlite;
+
+pub use memory::MemoryStore;
+pub use sqlite::SqliteStore;
+
+// Persistence for indexed events. Implementations must be cheap to share across tasks,
+// the service, the dataloader and the resolvers all hold the same `Arc<dyn Store>`.
+#[async_trait::async_trait]
+pub trait EventStore: Send + Sync {
+    async fn get_event_by_id(&self, id: &ID) -> Result<Option<Event>>;
+
+    // Returns one page of the chain's matching events, sorted newest first.
+    async fn list_events(&self, chain_id: &ID, filter: Option<&EventFilterInput>, page: &EventPage) -> Result<Vec<Event>>;
+
+    async fn count_events(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> Result<u64>;
+
+    // Matching events counted per pallet and event name, most frequent first, ties by name.
+    async fn count_events_by_name(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> Result<Vec<EventNameCount>>;
+
+    // Matching events counted per `bucket_ms` wide slot of their timestamp, oldest first.
+    // Empty slots are absent.
+    async fn count_events_by_time(
+        &self,
+        chain_id: &ID,
+        filter: Option<&EventFilterInput>,
+        bucket_ms: i64,
+    ) -> Result<Vec<TimeBucketCount>>;
+
+    // Aggregates over the value at `path` in the data of each matching event, folded as
+    // events are read rather than gathered first.
+    async fn event_data_stats(
+        &self,
+        chain_id: &ID,
+        filter: Option<&EventFilterInput>,
+        path: &[PathSegment<'_>],
+    ) -> Result<DataFieldStats>;
+
+    // Events of the given blocks, ordered by position. Blocks without events are absent.
+    async fn list_events_in_blocks(&self, chain_id: &ID, block_numbers: &[u64]) -> Result<Vec<Event>>;
+
+    // Events emitted by the given extrinsics, ordered by position.
+    async fn list_events_for_extrinsics(&self, chain_id: &ID, extrinsic_ids: &[ID]) -> Result<Vec<Event>>;
+
+    async fn insert_event(&self, event: Event) -> Result<()>;
+
+    // Deletes the chain's events with a timestamp before `before`, returns how many.
+    async fn delete_events_before(&self, chain_id: &ID, before: DateTime<Utc>) -> Result<u64>;
+
+    // Highest block number seen for the given chain, `None` if nothing has been indexed yet.
+    async fn max_block_number(&self, chain_id: &ID) -> Result<Option<u64>>;
+}
+
+// Persistence for chain level metadata.
+#[async_trait::async_trait]
+pub trait ChainStore: Send + Sync {
+    async fn get_chain_info(&self, id: &ID) -> Result<Option<ChainInfo>>;
+
+    async fn get_chain_infos_batch(&self, ids: &[ID]) -> Result<HashMap<ID, ChainInfo>>;
+
+    async fn upsert_chain_info(&self, chain_info: ChainInfo) -> Result<()>;
+
+    // Raw SCALE encoded runtime metadata, one row per runtime version.
+    async fn save_runtime_metadata(&self, chain_id: &ID, metadata: RawMetadata) -> Result<()>;
+
+    // Metadata of the most recent runtime version seen for the chain.
+    async fn get_runtime_metadata(&self, chain_id: &ID) -> Result<Option<RawMetadata>>;
+
+    // Chains registered at runtime, on top of the configured ones, in registration order.
+    async fn list_registered_chains(&self) -> Result<Vec<ChainConfig>>;
+
+    async fn save_registered_chain(&self, chain: ChainConfig) -> Result<()>;
+
+    async fn delete_registered_chain(&self, id: &ID) -> Result<()>;
+
+    // Cheapest round trip to the backend, for health checks.
+    async fn ping(&self) -> Result<()>;
+}
+
+// Persistence for the blocks of each chain, keyed by (chain, number).
+#[async_trait::async_trait]
+pub trait BlockStore: Send + Sync {
+    async fn get_block_by_number(&self, chain_id: &ID, number: u64) -> Result<Option<Block>>;
+
+    async fn get_block_by_hash(&self, chain_id: &ID, hash: &str) -> Result<Option<Block>>;
+
+    async fn get_blocks_batch(&self, chain_id: &ID, numbers: &[u64]) -> Result<HashMap<u64, Block>>;
+
+    // Returns one page of the chain's blocks, sorted newest first.
+    async fn list_blocks(&self, chain_id: &ID, finalized_only: bool, page: &BlockPage) -> Result<Vec<Block>>;
+
+    // Replaces any block previously stored at the same number.
+    async fn upsert_block(&self, block: Block) -> Result<()>;
+
+    // Highest block of the chain, finalized or not, `None` if none is stored.
+    async fn indexed_block_height(&self, chain_id: &ID) -> Result<Option<u64>>;
+
+    // Highest finalized block of the chain, `None` if none is stored.
+    async fn finalized_block_height(&self, chain_id: &ID) -> Result<Option<u64>>;
+
+    // Marks blocks up to `number`, and their events and extrinsics, finalized. Returns the
+    // numbers of the blocks that were not finalized before, ascending.
+    async fn finalize_blocks(&self, chain_id: &ID, number: u64) -> Result<Vec<u64>>;
+
+    // Deletes blocks from `number` on, with their events and extrinsics, and returns the
+    // deleted blocks ascending. Callers make sure none of them is finalized.
+    async fn rollback_blocks(&self, chain_id: &ID, number: u64) -> Result<Vec<Block>>;
+}
+
+// Persistence for decoded extrinsics, keyed by (chain, id).
+#[async_trait::async_trait]
+pub trait ExtrinsicStore: Send + Sync {
+    async fn get_extrinsic(&self, chain_id: &ID, id: &ID) -> Result<Option<Extrinsic>>;
+
+    async fn get_extrinsics_batch(&self, chain_id: &ID, ids: &[ID]) -> Result<HashMap<ID, Extrinsic>>;
+
+    // Returns one page of the chain's matching extrinsics, sorted newest first.
+    async fn list_extrinsics(
+        &self,
+        chain_id: &ID,
+        filter: Option<&ExtrinsicFilterInput>,
+        page: &ExtrinsicPage,
+    ) -> Result<Vec<Extrinsic>>;
+
+    async fn insert_extrinsic(&self, extrinsic: Extrinsic) -> Result<()>;
+}
+
+// Lookups by account across events and extrinsics. Accounts are lowercase 0x-prefixed hex,
+// the events of an account are listed through `EventFilterInput.account`.
+#[async_trait::async_trait]
+pub trait AccountStore: Send + Sync {
+    // Lowest and highest block the account shows up in, as a signer or in event data.
+    // `None` if it never did.
+    async fn account_activity(&self, chain_id: &ID, account: &str) -> Result<Option<(u64, u64)>>;
+}
+
+// Checkpoints of historical backfills, one per chain.
+#[async_trait::async_trait]
+pub trait BackfillStore: Send + Sync {
+    async fn get_backfill(&self, chain_id: &ID) -> Result<Option<BackfillStatus>>;
+
+    // Replaces the chain's previous backfill, if any.
+    async fn save_backfill(&self, status: BackfillStatus) -> Result<()>;
+
+    // Writes a batch of backfilled blocks, with their extrinsics and events, together with
+    // the checkpoint after it. All or nothing.
+    async fn save_backfill_batch(&self, blocks: Vec<IndexedBlock>, status: BackfillStatus) -> Result<()>;
+}
+
+// API keys issued by `rotateApiKeys`, only their hashes are stored.
+#[async_trait::async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    async fn list_api_keys(&self) -> Result<Vec<StoredApiKey>>;
+
+    async fn get_api_key_by_hash(&self, key_hash: &str) -> Result<Option<StoredApiKey>>;
+
+    async fn has_api_key_named(&self, name: &str) -> Result<bool>;
+
+    // Revokes the stored keys with the same name in favour of `key`.
+    async fn rotate_api_key(&self, key: StoredApiKey) -> Result<()>;
+}
+
+// Keyset page in newest-first order. Bounds are exclusive.
+#[derive(Debug, Clone)]
+pub struct Page<K> {
+    // Only entries older than this position
+    pub after: Option<K>,
+    // Only entries newer than this position
+    pub before: Option<K>,
+    pub limit: usize,
+    // Take `limit` entries from the oldest end of the range instead of the newest
+    pub from_oldest: bool,
+}
+
+// Not derived, that would require `K: Default`
+impl<K> Default for Page<K> {
+    fn default() -> Self {
+        Self {
+            after: None,
+            before: None,
+            limit: 0,
+            from_oldest: false,
+        }
+    }
+}
+
+impl<K: Ord + Copy> Page<K> {
+    pub fn contains(&self, position: K) -> bool {
+        self.after.is_none_or(|after| position < after) && self.before.is_none_or(|before| position > before)
+    }
+}
+
+// Events are positioned by (block_number, event_index)
+pub type EventPage = Page<EventCursor>;
+
+// Blocks are positioned by their number
+pub type BlockPage = Page<u64>;
+
+pub type ExtrinsicPage = Page<ExtrinsicCursor>;
+
+// A block with the extrinsics and events decoded from it
+#[derive(Debug, Clone)]
+pub struct IndexedBlock {
+    pub block: Block,
+    pub extrinsics: Vec<Extrinsic>,
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RawMetadata {
+    pub spec_version: u32,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredApiKey {
+    pub id: ID,
+    pub name: String,
+    pub key_hash: String, // blake2b-256 of the key, 0x-prefixed hex
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Convenience trait so a single trait object can serve every part.
+pub trait Store: EventStore + ChainStore + BlockStore + ExtrinsicStore + AccountStore + BackfillStore + ApiKeyStore {}
+
+impl<T: EventStore + ChainStore + BlockStore + ExtrinsicStore + AccountStore + BackfillStore + ApiKeyStore> Store for T {}
+
+// Build the storage backend selected in `AppConfig.storage`. The in-memory store is
+// seeded with a few mock blocks and events for each of `chain_ids`.
+pub fn from_config(config: &StorageConfig, chain_ids: &[ID]) -> Result<Arc<dyn Store>> {
+    match config.backend {
+        StorageBackend::Memory => {
+            info!("Using in-memory event store");
+            Ok(Arc::new(MemoryStore::with_mock_data(chain_ids)))
+        }
+        StorageBackend::Sqlite => Ok(Arc::new(SqliteStore::open(&config.sqlite_path)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::Address;
+    use crate::models::EventDataFilterInput;
+    use serde_json::{json, Value};
+
+    const ALICE_HEX: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+    const ALICE_SS58: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB_HEX: &str = "0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48";
+    const BOB_SS58: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    fn event(block_number: u64, event_index: u32, extrinsic_id: Option<&str>, name: (&str, &str), data: Value) -> Event {
+        Event {
+            id: ID::from(format!("e{}-{}", block_number, event_index)),
+            block_number,
+            event_index,
+            extrinsic_id: extrinsic_id.map(str::to_string),
+            timestamp: DateTime::from_timestamp(1_700_000_000 + block_number as i64 * 6, 0).unwrap(),
+            pallet_name: name.0.to_string(),
+            event_name: name.1.to_string(),
+            data,
+            finalized: block_number < 3,
+            chain_id: ID::from("dev"),
+        }
+    }
+
+    fn events() -> Vec<Event> {
+        vec![
+            event(1, 0, Some("1-1"), ("Balances", "Transfer"), json!({ "from": ALICE_HEX, "to": BOB_HEX, "amount": "1000" })),
+            // 2^64, past u64 as well as i64
+            event(2, 0, Some("2-1"), ("Balances", "Transfer"), json!({ "from": BOB_HEX, "to": ALICE_HEX, "amount": "18446744073709551616" })),
+            event(2, 1, None, ("Balances", "Deposit"), json!({ "who": ALICE_HEX, "amount": u128::MAX.to_string() })),
+            event(3, 0, Some("3-0"), ("System", "ExtrinsicSuccess"), json!({ "dispatch_info": { "weight": { "ref_time": 125_000_000 } } })),
+            event(3, 1, Some("3-1"), ("Balances", "Transfer"), json!({ "from": ALICE_HEX, "to": BOB_HEX, "amount": i64::MAX })),
+        ]
+    }
+
+    fn data(path: &str) -> EventDataFilterInput {
+        EventDataFilterInput {
+            path: path.to_string(),
+            eq: None,
+            gt: None,
+            gte: None,
+            lt: None,
+            lte: None,
+            exists: None,
+        }
+    }
+
+    fn with_data(conditions: Vec<EventDataFilterInput>) -> EventFilterInput {
+        EventFilterInput {
+            data: Some(conditions),
+            ..EventFilterInput::default()
+        }
+    }
+
+    async fn matching_ids(store: &dyn Store, filter: &EventFilterInput) -> Vec<String> {
+        let page = EventPage { limit: 100, from_oldest: true, ..EventPage::default() };
+        let events = store.list_events(&ID::from("dev"), Some(filter), &page).await.unwrap();
+        let mut ids: Vec<String> = events.into_iter().map(|e| e.id.to_string()).collect();
+        ids.sort();
+        ids
+    }
+
+    // Both backends evaluate every filter the same way
+    #[tokio::test]
+    async fn memory_and_sqlite_filters_agree() {
+        let memory = MemoryStore::default();
+        let sqlite = SqliteStore::open(":memory:").unwrap();
+        for event in events() {
+            memory.insert_event(event.clone()).await.unwrap();
+            sqlite.insert_event(event).await.unwrap();
+        }
+
+        let cases: Vec<(&str, EventFilterInput, &[&str])> = vec![
+            ("amount above i64::MAX", with_data(vec![EventDataFilterInput { gt: Some(i64::MAX.to_string()), ..data("amount") }]), &["e2-0", "e2-1"]),
+            ("amount from i64::MAX", with_data(vec![EventDataFilterInput { gte: Some(i64::MAX.to_string()), ..data("amount") }]), &["e2-0", "e2-1", "e3-1"]),
+            ("amount below 2^64", with_data(vec![EventDataFilterInput { lt: Some("18446744073709551616".to_string()), ..data("amount") }]), &["e1-0", "e3-1"]),
+            ("u128::MAX", with_data(vec![EventDataFilterInput { lte: Some(u128::MAX.to_string()), gte: Some(u128::MAX.to_string()), ..data("amount") }]), &["e2-1"]),
+            ("number equals string", with_data(vec![EventDataFilterInput { eq: Some(json!(1000)), ..data("amount") }]), &["e1-0"]),
+            ("string equals number", with_data(vec![EventDataFilterInput { eq: Some(json!(i64::MAX.to_string())), ..data("amount") }]), &["e3-1"]),
+            ("2^64 as string", with_data(vec![EventDataFilterInput { eq: Some(json!("18446744073709551616")), ..data("amount") }]), &["e2-0"]),
+            ("SS58 equals hex", with_data(vec![EventDataFilterInput { eq: Some(json!(ALICE_SS58)), ..data("to") }]), &["e2-0"]),
+            ("hex equals hex", with_data(vec![EventDataFilterInput { eq: Some(json!(ALICE_HEX)), ..data("to") }]), &["e2-0"]),
+            ("SS58 sender", with_data(vec![EventDataFilterInput { eq: Some(json!(BOB_SS58)), ..data("from") }]), &["e2-0"]),
+            ("nested path", with_data(vec![EventDataFilterInput { gte: Some("1".to_string()), ..data("dispatch_info.weight.ref_time") }]), &["e3-0"]),
+            ("missing field", with_data(vec![EventDataFilterInput { exists: Some(false), ..data("amount") }]), &["e3-0"]),
+            (
+                "account in any field",
+                EventFilterInput { account: Some(Address::parse(ALICE_SS58).unwrap()), ..EventFilterInput::default() },
+                &["e1-0", "e2-0", "e2-1", "e3-1"],
+            ),
+            (
+                "pallet in list",
+                EventFilterInput { pallet_name_in: Some(vec!["System".to_string()]), ..EventFilterInput::default() },
+                &["e3-0"],
+            ),
+            (
+                "not to Bob",
+                EventFilterInput {
+                    event_name_starts_with: Some("Tr".to_string()),
+                    not: Some(Box::new(with_data(vec![EventDataFilterInput { eq: Some(json!(BOB_SS58)), ..data("to") }]))),
+                    ..EventFilterInput::default()
+                },
+                &["e2-0"],
+            ),
+            (
+                "or",
+                EventFilterInput {
+                    or: Some(vec![
+                        EventFilterInput { extrinsic_id_is_null: Some(true), ..EventFilterInput::default() },
+                        EventFilterInput { block_number_gte: Some(3), ..EventFilterInput::default() },
+                    ]),
+                    ..EventFilterInput::default()
+                },
+                &["e2-1", "e3-0", "e3-1"],
+            ),
+            ("unfinalized", EventFilterInput { finalized: Some(false), ..EventFilterInput::default() }, &["e3-0", "e3-1"]),
+        ];
+
+        for (name, filter, expected) in cases {
+            filter.validate().unwrap();
+            assert_eq!(matching_ids(&memory, &filter).await, expected, "memory store: {}", name);
+            assert_eq!(matching_ids(&sqlite, &filter).await, expected, "sqlite store: {}", name);
+        }
+    }
+
+    // The indexed height counts blocks without events, the finalized one only finalized blocks
+    #[tokio::test]
+    async fn memory_and_sqlite_block_heights_agree() {
+        let memory = MemoryStore::default();
+        let sqlite = SqliteStore::open(":memory:").unwrap();
+        let stores: [(&str, &dyn Store); 2] = [("memory", &memory), ("sqlite", &sqlite)];
+        let chain_id = ID::from("dev");
+        for (name, store) in stores {
+            assert_eq!(store.indexed_block_height(&chain_id).await.unwrap(), None, "{} store", name);
+            for event in events() {
+                store.insert_event(event).await.unwrap();
+            }
+            for number in 1..=4 {
+                let block = Block {
+                    finalized: number < 3,
+                    ..Block::mock(&chain_id, number, String::new(), DateTime::from_timestamp(0, 0).unwrap())
+                };
+                store.upsert_block(block).await.unwrap();
+            }
+            assert_eq!(store.indexed_block_height(&chain_id).await.unwrap(), Some(4), "{} store", name);
+            assert_eq!(store.finalized_block_height(&chain_id).await.unwrap(), Some(2), "{} store", name);
+            assert_eq!(store.indexed_block_height(&ID::from("other")).await.unwrap(), None, "{} store", name);
+        }
+    }
+}
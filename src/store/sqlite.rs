@@ -0,0 +1,1298 @@
+use super::{AccountStore, ApiKeyStore, BackfillStore, BlockPage, BlockStore, ChainStore, EventPage, EventStore, ExtrinsicPage, ExtrinsicStore, IndexedBlock, RawMetadata, StoredApiKey};
+use crate::config::ChainConfig;
+use crate::errors::{AppError, Result};
+use crate::models::{
+    BackfillState, BackfillStatus, Block, ChainInfo, CompareOp, DataFieldStats, DataOp, Event, EventFilterInput, EventNameCount, EventPredicate,
+    EventTextField, Extrinsic, ExtrinsicFilterInput, PathSegment, Scope, TextOp, TimeBucketCount,
+};
+use async_graphql::ID;
+use chrono::{DateTime, Utc};
+use rusqlite::types::{ToSql, Type};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+// Schema migrations, applied in order. The index of each entry + 1 is stored in
+// `PRAGMA user_version`, so entries must never be edited or reordered once released.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    r#"
+    CREATE TABLE chain_infos (
+        id           TEXT PRIMARY KEY NOT NULL,
+        name         TEXT NOT NULL,
+        version      TEXT NOT NULL,
+        token_symbol TEXT NOT NULL,
+        decimals     INTEGER NOT NULL,
+        ss58_prefix  INTEGER NOT NULL,
+        last_updated INTEGER NOT NULL
+    );
+
+    CREATE TABLE events (
+        id           TEXT PRIMARY KEY NOT NULL,
+        chain_id     TEXT NOT NULL,
+        block_number INTEGER NOT NULL,
+        extrinsic_id TEXT,
+        timestamp    INTEGER NOT NULL,
+        pallet_name  TEXT NOT NULL,
+        event_name   TEXT NOT NULL,
+        data         TEXT NOT NULL
+    );
+
+    CREATE INDEX idx_events_block_number ON events (block_number);
+    CREATE INDEX idx_events_pallet_event ON events (pallet_name, event_name);
+    CREATE INDEX idx_events_timestamp ON events (timestamp);
+    "#,
+    // 2: runtime metadata per runtime version
+    r#"
+    CREATE TABLE runtime_metadata (
+        chain_id     TEXT NOT NULL,
+        spec_version INTEGER NOT NULL,
+        metadata     BLOB NOT NULL,
+        PRIMARY KEY (chain_id, spec_version)
+    );
+    "#,
+    // 3: events are always queried per chain
+    r#"
+    CREATE INDEX idx_events_chain_block ON events (chain_id, block_number);
+    "#,
+    // 4: position within the block, events are ordered and paginated by (block_number, event_index)
+    r#"
+    ALTER TABLE events ADD COLUMN event_index INTEGER NOT NULL DEFAULT 0;
+    DROP INDEX idx_events_chain_block;
+    CREATE INDEX idx_events_chain_position ON events (chain_id, block_number, event_index);
+    "#,
+    // 5: blocks of the canonical chain
+    r#"
+    CREATE TABLE blocks (
+        chain_id        TEXT NOT NULL,
+        number          INTEGER NOT NULL,
+        hash            TEXT NOT NULL,
+        parent_hash     TEXT NOT NULL,
+        state_root      TEXT NOT NULL,
+        extrinsics_root TEXT NOT NULL,
+        timestamp       INTEGER NOT NULL,
+        author          TEXT,
+        finalized       INTEGER NOT NULL,
+        PRIMARY KEY (chain_id, number)
+    );
+
+    CREATE INDEX idx_blocks_chain_hash ON blocks (chain_id, hash);
+    "#,
+    // 6: decoded extrinsics, linked to their events through `events.extrinsic_id`
+    r#"
+    CREATE TABLE extrinsics (
+        chain_id        TEXT NOT NULL,
+        id              TEXT NOT NULL,
+        block_number    INTEGER NOT NULL,
+        extrinsic_index INTEGER NOT NULL,
+        hash            TEXT NOT NULL,
+        signer          TEXT,
+        nonce           INTEGER,
+        tip             TEXT,
+        fee             TEXT,
+        success         INTEGER NOT NULL,
+        pallet_name     TEXT NOT NULL,
+        call_name       TEXT NOT NULL,
+        args            TEXT NOT NULL,
+        PRIMARY KEY (chain_id, id)
+    );
+
+    CREATE INDEX idx_extrinsics_chain_position ON extrinsics (chain_id, block_number, extrinsic_index);
+    CREATE INDEX idx_extrinsics_chain_signer ON extrinsics (chain_id, signer);
+    CREATE INDEX idx_events_chain_extrinsic ON events (chain_id, extrinsic_id);
+    "#,
+    // 7: finality of the block events and extrinsics come from. Rows stored so far were
+    // ingested from finalized heads only.
+    r#"
+    ALTER TABLE events ADD COLUMN finalized INTEGER NOT NULL DEFAULT 1;
+    ALTER TABLE extrinsics ADD COLUMN finalized INTEGER NOT NULL DEFAULT 1;
+    "#,
+    // 8: backfill checkpoints, one job per chain
+    r#"
+    CREATE TABLE backfills (
+        chain_id   TEXT PRIMARY KEY NOT NULL,
+        from_block INTEGER NOT NULL,
+        to_block   INTEGER NOT NULL,
+        next_block INTEGER NOT NULL,
+        state      TEXT NOT NULL,
+        error      TEXT,
+        started_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    "#,
+    // 9: chains registered at runtime, admin API keys and the index behind event purges
+    r#"
+    CREATE TABLE registered_chains (
+        id            TEXT PRIMARY KEY NOT NULL,
+        config        TEXT NOT NULL, -- ChainConfig as JSON
+        registered_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE api_keys (
+        id         TEXT PRIMARY KEY NOT NULL,
+        key_hash   TEXT NOT NULL UNIQUE,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX idx_events_chain_timestamp ON events (chain_id, timestamp);
+    "#,
+    // 10: named, scoped API keys. Keys issued before were admin keys.
+    r#"
+    ALTER TABLE api_keys ADD COLUMN name TEXT NOT NULL DEFAULT 'admin';
+    ALTER TABLE api_keys ADD COLUMN scopes TEXT NOT NULL DEFAULT 'admin'; -- space separated
+    "#,
+    // 11: accounts each event's data holds, see `mentions_account`. Kept up to date by
+    // triggers, indexing the events stored so far. `INSERT OR REPLACE` fires no delete
+    // trigger, so the insert trigger drops what the replaced row left behind.
+    r#"
+    CREATE TABLE event_accounts (
+        account  TEXT NOT NULL, -- lowercase 0x-prefixed hex
+        event_id TEXT NOT NULL,
+        PRIMARY KEY (account, event_id)
+    ) WITHOUT ROWID;
+
+    CREATE INDEX idx_event_accounts_event ON event_accounts (event_id);
+
+    CREATE TRIGGER events_index_accounts AFTER INSERT ON events BEGIN
+        DELETE FROM event_accounts WHERE event_id = new.id;
+        INSERT OR IGNORE INTO event_accounts (account, event_id)
+        SELECT lower(value), new.id FROM json_tree(new.data)
+        WHERE type = 'text' AND length(value) = 66 AND substr(value, 1, 2) IN ('0x', '0X')
+            AND substr(value, 3) NOT GLOB '*[^0-9a-fA-F]*';
+    END;
+
+    CREATE TRIGGER events_unindex_accounts AFTER DELETE ON events BEGIN
+        DELETE FROM event_accounts WHERE event_id = old.id;
+    END;
+
+    INSERT OR IGNORE INTO event_accounts (account, event_id)
+    SELECT lower(t.value), e.id FROM events e, json_tree(e.data) t
+    WHERE t.type = 'text' AND length(t.value) = 66 AND substr(t.value, 1, 2) IN ('0x', '0X')
+        AND substr(t.value, 3) NOT GLOB '*[^0-9a-fA-F]*';
+    "#,
+    // 12: authentication looks stored keys up by name as well as by hash
+    r#"
+    CREATE INDEX idx_api_keys_name ON api_keys (name);
+    "#,
+];
+
+const EVENT_COLUMNS: &str =
+    "id, chain_id, block_number, extrinsic_id, timestamp, pallet_name, event_name, data, event_index, finalized";
+
+const CHAIN_INFO_COLUMNS: &str =
+    "id, name, version, token_symbol, decimals, ss58_prefix, last_updated";
+
+const BLOCK_COLUMNS: &str =
+    "chain_id, number, hash, parent_hash, state_root, extrinsics_root, timestamp, author, finalized";
+
+const EXTRINSIC_COLUMNS: &str = "chain_id, id, block_number, extrinsic_index, hash, signer, nonce, tip, fee, success, \
+     pallet_name, call_name, args, finalized";
+
+const BACKFILL_COLUMNS: &str =
+    "chain_id, from_block, to_block, next_block, state, error, started_at, updated_at";
+
+const API_KEY_COLUMNS: &str = "id, name, key_hash, scopes, created_at";
+
+// Embedded SQLite store. rusqlite is blocking, so every call is moved onto tokio's
+// blocking pool and serialised through a single connection.
+#[derive(Clone)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        run_migrations(&mut conn)?;
+        info!(path = %path.display(), "Opened SQLite event store");
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn
+                .lock()
+                .map_err(|e| AppError::Internal(format!("Failed to lock SQLite connection: {}", e)))?;
+            f(&conn).map_err(AppError::from)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("SQLite task failed: {}", e)))?
+    }
+}
+
+fn run_migrations(conn: &mut Connection) -> Result<()> {
+    let current: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = idx + 1;
+        info!(version, "Applying SQLite migration");
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn timestamp_from_millis(idx: usize, millis: i64) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(millis).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            idx,
+            Type::Integer,
+            format!("timestamp out of range: {}", millis).into(),
+        )
+    })
+}
+
+fn row_to_event(row: &Row<'_>) -> rusqlite::Result<Event> {
+    Ok(Event {
+        id: ID::from(row.get::<_, String>(0)?),
+        chain_id: ID::from(row.get::<_, String>(1)?),
+        block_number: row.get::<_, i64>(2)? as u64,
+        event_index: row.get(8)?,
+        extrinsic_id: row.get(3)?,
+        timestamp: timestamp_from_millis(4, row.get(4)?)?,
+        pallet_name: row.get(5)?,
+        event_name: row.get(6)?,
+        data: row.get(7)?,
+        finalized: row.get(9)?,
+    })
+}
+
+fn row_to_chain_info(row: &Row<'_>) -> rusqlite::Result<ChainInfo> {
+    Ok(ChainInfo {
+        id: ID::from(row.get::<_, String>(0)?),
+        name: row.get(1)?,
+        version: row.get(2)?,
+        token_symbol: row.get(3)?,
+        decimals: row.get(4)?,
+        ssv58_prefix: row.get(5)?,
+        last_updated: timestamp_from_millis(6, row.get(6)?)?,
+    })
+}
+
+fn row_to_block(row: &Row<'_>) -> rusqlite::Result<Block> {
+    Ok(Block {
+        chain_id: ID::from(row.get::<_, String>(0)?),
+        number: row.get::<_, i64>(1)? as u64,
+        hash: row.get(2)?,
+        parent_hash: row.get(3)?,
+        state_root: row.get(4)?,
+        extrinsics_root: row.get(5)?,
+        timestamp: timestamp_from_millis(6, row.get(6)?)?,
+        author: row.get(7)?,
+        finalized: row.get(8)?,
+    })
+}
+
+fn row_to_extrinsic(row: &Row<'_>) -> rusqlite::Result<Extrinsic> {
+    Ok(Extrinsic {
+        chain_id: ID::from(row.get::<_, String>(0)?),
+        id: ID::from(row.get::<_, String>(1)?),
+        block_number: row.get::<_, i64>(2)? as u64,
+        index: row.get(3)?,
+        hash: row.get(4)?,
+        signer: row.get(5)?,
+        nonce: row.get::<_, Option<i64>>(6)?.map(|n| n as u64),
+        tip: row.get(7)?,
+        fee: row.get(8)?,
+        success: row.get(9)?,
+        pallet_name: row.get(10)?,
+        call_name: row.get(11)?,
+        args: row.get(12)?,
+        finalized: row.get(13)?,
+    })
+}
+
+fn row_to_backfill(row: &Row<'_>) -> rusqlite::Result<BackfillStatus> {
+    let state: String = row.get(4)?;
+    Ok(BackfillStatus {
+        chain_id: ID::from(row.get::<_, String>(0)?),
+        from_block: row.get::<_, i64>(1)? as u64,
+        to_block: row.get::<_, i64>(2)? as u64,
+        next_block: row.get::<_, i64>(3)? as u64,
+        state: BackfillState::parse(&state).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(4, Type::Text, format!("unknown backfill state: {}", state).into())
+        })?,
+        error: row.get(5)?,
+        started_at: timestamp_from_millis(6, row.get(6)?)?,
+        updated_at: timestamp_from_millis(7, row.get(7)?)?,
+    })
+}
+
+fn row_to_api_key(row: &Row<'_>) -> rusqlite::Result<StoredApiKey> {
+    let scopes: String = row.get(3)?;
+    Ok(StoredApiKey {
+        id: ID::from(row.get::<_, String>(0)?),
+        name: row.get(1)?,
+        key_hash: row.get(2)?,
+        scopes: scopes.split_whitespace().filter_map(Scope::parse).collect(),
+        created_at: timestamp_from_millis(4, row.get(4)?)?,
+    })
+}
+
+type SqlParams = Vec<Box<dyn ToSql + Send>>;
+
+// Translate the chain scope and filter into a WHERE clause plus its bound parameters.
+fn filter_to_sql(chain_id: &ID, filter: Option<&EventFilterInput>) -> (String, SqlParams) {
+    let mut params: SqlParams = vec![Box::new(chain_id.to_string())];
+    let mut where_clause = "WHERE chain_id = ?".to_string();
+    if let Some(f) = filter {
+        where_clause.push_str(" AND ");
+        where_clause.push_str(&filter_clause(f, &mut params));
+    }
+    (where_clause, params)
+}
+
+// Mirrors `EventFilterInput::matches`. Every condition evaluates to 0 or 1, never NULL,
+// so `NOT` behaves like the in-process negation.
+fn filter_clause(filter: &EventFilterInput, params: &mut SqlParams) -> String {
+    let mut clauses: Vec<String> = filter
+        .predicates()
+        .iter()
+        .map(|p| predicate_clause(p, params))
+        .collect();
+    for nested in filter.and.iter().flatten() {
+        clauses.push(filter_clause(nested, params));
+    }
+    if let Some(or) = filter.or.as_ref().filter(|or| !or.is_empty()) {
+        let alternatives: Vec<String> = or.iter().map(|f| filter_clause(f, params)).collect();
+        clauses.push(format!("({})", alternatives.join(" OR ")));
+    }
+    if let Some(not) = &filter.not {
+        clauses.push(format!("NOT {}", filter_clause(not, params)));
+    }
+    if clauses.is_empty() {
+        "1".to_string()
+    } else {
+        format!("({})", clauses.join(" AND "))
+    }
+}
+
+fn predicate_clause(predicate: &EventPredicate<'_>, params: &mut SqlParams) -> String {
+    match *predicate {
+        EventPredicate::Data(ref path, ref op) => data_clause(path, op, params),
+        EventPredicate::Text(field, op) => {
+            let column = match field {
+                EventTextField::PalletName => "pallet_name",
+                EventTextField::EventName => "event_name",
+                EventTextField::ExtrinsicId => "extrinsic_id",
+            };
+            // LIKE is case-insensitive in SQLite, substr/instr keep these exact
+            let condition = match op {
+                TextOp::Eq(v) => {
+                    params.push(Box::new(v.to_string()));
+                    format!("{} = ?", column)
+                }
+                TextOp::In(values) | TextOp::Nin(values) => {
+                    params.extend(values.iter().map(|v| Box::new(v.clone()) as Box<dyn ToSql + Send>));
+                    let not = if matches!(op, TextOp::Nin(_)) { "NOT " } else { "" };
+                    format!("{} {}IN ({})", column, not, vec!["?"; values.len()].join(", "))
+                }
+                TextOp::StartsWith(prefix) => {
+                    params.push(Box::new(prefix.chars().count() as i64));
+                    params.push(Box::new(prefix.to_string()));
+                    format!("substr({}, 1, ?) = ?", column)
+                }
+                TextOp::Contains(needle) => {
+                    params.push(Box::new(needle.to_string()));
+                    format!("instr({}, ?) > 0", column)
+                }
+            };
+            format!("COALESCE({}, 0)", condition)
+        }
+        EventPredicate::ExtrinsicIdIsNull(true) => "extrinsic_id IS NULL".to_string(),
+        EventPredicate::ExtrinsicIdIsNull(false) => "extrinsic_id IS NOT NULL".to_string(),
+        EventPredicate::BlockNumberGte(bn) => {
+            params.push(Box::new(bn as i64));
+            "block_number >= ?".to_string()
+        }
+        EventPredicate::BlockNumberLte(bn) => {
+            params.push(Box::new(bn as i64));
+            "block_number <= ?".to_string()
+        }
+        EventPredicate::TimestampGte(ms) => {
+            params.push(Box::new(ms));
+            "timestamp >= ?".to_string()
+        }
+        EventPredicate::TimestampLte(ms) => {
+            params.push(Box::new(ms));
+            "timestamp <= ?".to_string()
+        }
+        EventPredicate::Finalized(finalized) => {
+            params.push(Box::new(finalized));
+            "finalized = ?".to_string()
+        }
+        EventPredicate::Account(ref account) => {
+            params.push(Box::new(account.clone()));
+            "id IN (SELECT event_id FROM event_accounts WHERE account = ?)".to_string()
+        }
+    }
+}
+
+// Mirrors `ExtrinsicFilterInput::matches`.
+fn extrinsic_filter_to_sql(chain_id: &ID, filter: Option<&ExtrinsicFilterInput>) -> (String, SqlParams) {
+    let mut params: SqlParams = vec![Box::new(chain_id.to_string())];
+    let mut where_clause = "WHERE chain_id = ?".to_string();
+    let Some(f) = filter else {
+        return (where_clause, params);
+    };
+    let mut text = |column: &str, eq: &Option<String>, any_of: &Option<Vec<String>>, params: &mut SqlParams| {
+        if let Some(eq) = eq {
+            where_clause.push_str(&format!(" AND {} = ?", column));
+            params.push(Box::new(eq.clone()));
+        }
+        if let Some(any_of) = any_of {
+            where_clause.push_str(&format!(" AND {} IN ({})", column, vec!["?"; any_of.len()].join(", ")));
+            params.extend(any_of.iter().map(|v| Box::new(v.clone()) as Box<dyn ToSql + Send>));
+        }
+    };
+    text("pallet_name", &f.pallet_name_eq, &f.pallet_name_in, &mut params);
+    text("call_name", &f.call_name_eq, &f.call_name_in, &mut params);
+    text("signer", &f.signer_eq.map(|s| s.to_hex()), &None, &mut params);
+    match f.signed {
+        Some(true) => where_clause.push_str(" AND signer IS NOT NULL"),
+        Some(false) => where_clause.push_str(" AND signer IS NULL"),
+        None => {}
+    }
+    if let Some(success) = f.success {
+        where_clause.push_str(" AND success = ?");
+        params.push(Box::new(success));
+    }
+    if let Some(bn) = f.block_number_gte {
+        where_clause.push_str(" AND block_number >= ?");
+        params.push(Box::new(bn as i64));
+    }
+    if let Some(bn) = f.block_number_lte {
+        where_clause.push_str(" AND block_number <= ?");
+        params.push(Box::new(bn as i64));
+    }
+    if let Some(finalized) = f.finalized {
+        where_clause.push_str(" AND finalized = ?");
+        params.push(Box::new(finalized));
+    }
+    (where_clause, params)
+}
+
+// `data -> path` yields the JSON text of the value, which keeps integers beyond i64 exact
+// (`json_extract` would turn them into REALs). Numeric comparisons strip string quotes and
+// compare canonical digit strings by (length, text), like `compare_digits`.
+fn data_clause(path: &[PathSegment<'_>], op: &DataOp<'_>, params: &mut SqlParams) -> String {
+    params.push(Box::new(sqlite_json_path(path)));
+    match op {
+        DataOp::Exists(true) => "json_type(data, ?) IS NOT NULL".to_string(),
+        DataOp::Exists(false) => "json_type(data, ?) IS NULL".to_string(),
+        DataOp::EqJson(value) => {
+            params.push(Box::new(value.to_string()));
+            "COALESCE((data -> ?) = ?, 0)".to_string()
+        }
+        DataOp::Numeric(op, digits) => {
+            params.push(Box::new(digits.chars().count() as i64));
+            params.push(Box::new(digits.to_string()));
+            let op = match op {
+                CompareOp::Eq => "=",
+                CompareOp::Gt => ">",
+                CompareOp::Gte => ">=",
+                CompareOp::Lt => "<",
+                CompareOp::Lte => "<=",
+            };
+            format!(
+                "EXISTS (SELECT 1 FROM (SELECT CASE WHEN raw GLOB '\"*\"' THEN substr(raw, 2, length(raw) - 2) ELSE raw END AS d \
+                 FROM (SELECT data -> ? AS raw)) \
+                 WHERE d GLOB '[0-9]*' AND d NOT GLOB '*[^0-9]*' AND (d = '0' OR d NOT GLOB '0*') \
+                 AND (length(d), d) {} (?, ?))",
+                op
+            )
+        }
+    }
+}
+
+fn sqlite_json_path(path: &[PathSegment<'_>]) -> String {
+    let mut out = "$".to_string();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => out.push_str(&format!(".\"{}\"", key)),
+            PathSegment::Index(index) => out.push_str(&format!("[{}]", index)),
+        }
+    }
+    out
+}
+
+// Row writers shared by the single inserts and the batched backfill writes
+
+fn write_event(conn: &Connection, event: &Event) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO events ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            EVENT_COLUMNS
+        ),
+        params![
+            event.id.as_str(),
+            event.chain_id.as_str(),
+            event.block_number as i64,
+            event.extrinsic_id,
+            event.timestamp.timestamp_millis(),
+            event.pallet_name,
+            event.event_name,
+            event.data,
+            event.event_index,
+            event.finalized,
+        ],
+    )?;
+    Ok(())
+}
+
+fn write_block(conn: &Connection, block: &Block) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO blocks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            BLOCK_COLUMNS
+        ),
+        params![
+            block.chain_id.as_str(),
+            block.number as i64,
+            block.hash,
+            block.parent_hash,
+            block.state_root,
+            block.extrinsics_root,
+            block.timestamp.timestamp_millis(),
+            block.author,
+            block.finalized,
+        ],
+    )?;
+    Ok(())
+}
+
+fn write_extrinsic(conn: &Connection, extrinsic: &Extrinsic) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO extrinsics ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            EXTRINSIC_COLUMNS
+        ),
+        params![
+            extrinsic.chain_id.as_str(),
+            extrinsic.id.as_str(),
+            extrinsic.block_number as i64,
+            extrinsic.index,
+            extrinsic.hash,
+            extrinsic.signer,
+            extrinsic.nonce.map(|n| n as i64),
+            extrinsic.tip,
+            extrinsic.fee,
+            extrinsic.success,
+            extrinsic.pallet_name,
+            extrinsic.call_name,
+            extrinsic.args,
+            extrinsic.finalized,
+        ],
+    )?;
+    Ok(())
+}
+
+fn write_backfill(conn: &Connection, status: &BackfillStatus) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO backfills ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            BACKFILL_COLUMNS
+        ),
+        params![
+            status.chain_id.as_str(),
+            status.from_block as i64,
+            status.to_block as i64,
+            status.next_block as i64,
+            status.state.as_str(),
+            status.error,
+            status.started_at.timestamp_millis(),
+            status.updated_at.timestamp_millis(),
+        ],
+    )?;
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl EventStore for SqliteStore {
+    async fn get_event_by_id(&self, id: &ID) -> Result<Option<Event>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM events WHERE id = ?1", EVENT_COLUMNS),
+                params![id],
+                row_to_event,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn list_events(&self, chain_id: &ID, filter: Option<&EventFilterInput>, page: &EventPage) -> Result<Vec<Event>> {
+        let (mut where_clause, mut params) = filter_to_sql(chain_id, filter);
+        if let Some(after) = page.after {
+            where_clause.push_str(" AND (block_number, event_index) < (?, ?)");
+            params.push(Box::new(after.block_number as i64));
+            params.push(Box::new(after.event_index));
+        }
+        if let Some(before) = page.before {
+            where_clause.push_str(" AND (block_number, event_index) > (?, ?)");
+            params.push(Box::new(before.block_number as i64));
+            params.push(Box::new(before.event_index));
+        }
+        params.push(Box::new(page.limit as i64));
+        let order = if page.from_oldest { "ASC" } else { "DESC" };
+        let from_oldest = page.from_oldest;
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM events {} ORDER BY block_number {order}, event_index {order} LIMIT ?",
+                EVENT_COLUMNS, where_clause
+            );
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), row_to_event)?;
+            let mut events = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+            if from_oldest {
+                events.reverse();
+            }
+            Ok(events)
+        })
+        .await
+    }
+
+    async fn count_events(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> Result<u64> {
+        let (where_clause, params) = filter_to_sql(chain_id, filter);
+        self.with_conn(move |conn| {
+            let sql = format!("SELECT COUNT(*) FROM events {}", where_clause);
+            let count: i64 = conn.query_row(&sql, params_from_iter(params.iter().map(|p| p.as_ref())), |row| row.get(0))?;
+            Ok(count as u64)
+        })
+        .await
+    }
+
+    async fn count_events_by_name(&self, chain_id: &ID, filter: Option<&EventFilterInput>) -> Result<Vec<EventNameCount>> {
+        let (where_clause, params) = filter_to_sql(chain_id, filter);
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT pallet_name, event_name, COUNT(*) FROM events {} \
+                 GROUP BY pallet_name, event_name ORDER BY COUNT(*) DESC, pallet_name, event_name",
+                where_clause
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), |row| {
+                Ok(EventNameCount {
+                    pallet_name: row.get(0)?,
+                    event_name: row.get(1)?,
+                    count: row.get::<_, i64>(2)? as u64,
+                })
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+
+    // The bucket width is bound ahead of the filter's parameters, it comes first in the SQL.
+    async fn count_events_by_time(
+        &self,
+        chain_id: &ID,
+        filter: Option<&EventFilterInput>,
+        bucket_ms: i64,
+    ) -> Result<Vec<TimeBucketCount>> {
+        let (where_clause, filter_params) = filter_to_sql(chain_id, filter);
+        let mut params: SqlParams = vec![Box::new(bucket_ms), Box::new(bucket_ms)];
+        params.extend(filter_params);
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT (timestamp / ?) * ? AS bucket, COUNT(*) FROM events {} GROUP BY bucket ORDER BY bucket",
+                where_clause
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), |row| {
+                Ok(TimeBucketCount {
+                    start: timestamp_from_millis(0, row.get(0)?)?,
+                    count: row.get::<_, i64>(1)? as u64,
+                })
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+
+    // Summed here rather than in SQL, SQLite integers stop at i64.
+    async fn event_data_stats(
+        &self,
+        chain_id: &ID,
+        filter: Option<&EventFilterInput>,
+        path: &[PathSegment<'_>],
+    ) -> Result<DataFieldStats> {
+        let (where_clause, filter_params) = filter_to_sql(chain_id, filter);
+        let mut params: SqlParams = vec![Box::new(sqlite_json_path(path))];
+        params.extend(filter_params);
+        self.with_conn(move |conn| {
+            let sql = format!("SELECT data -> ? FROM events {}", where_clause);
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), |row| {
+                row.get::<_, Option<serde_json::Value>>(0)
+            })?;
+            let mut stats = DataFieldStats::default();
+            for value in rows {
+                if let Some(value) = value? {
+                    stats.add(&value);
+                }
+            }
+            Ok(stats)
+        })
+        .await
+    }
+
+    async fn list_events_in_blocks(&self, chain_id: &ID, block_numbers: &[u64]) -> Result<Vec<Event>> {
+        if block_numbers.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut params: SqlParams = vec![Box::new(chain_id.to_string())];
+        params.extend(block_numbers.iter().map(|n| Box::new(*n as i64) as Box<dyn ToSql + Send>));
+        let placeholders = vec!["?"; block_numbers.len()].join(", ");
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM events WHERE chain_id = ? AND block_number IN ({}) ORDER BY block_number, event_index",
+                EVENT_COLUMNS, placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), row_to_event)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn list_events_for_extrinsics(&self, chain_id: &ID, extrinsic_ids: &[ID]) -> Result<Vec<Event>> {
+        if extrinsic_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut params: SqlParams = vec![Box::new(chain_id.to_string())];
+        params.extend(extrinsic_ids.iter().map(|id| Box::new(id.to_string()) as Box<dyn ToSql + Send>));
+        let placeholders = vec!["?"; extrinsic_ids.len()].join(", ");
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM events WHERE chain_id = ? AND extrinsic_id IN ({}) ORDER BY block_number, event_index",
+                EVENT_COLUMNS, placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), row_to_event)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn insert_event(&self, event: Event) -> Result<()> {
+        self.with_conn(move |conn| write_event(conn, &event)).await
+    }
+
+    async fn delete_events_before(&self, chain_id: &ID, before: DateTime<Utc>) -> Result<u64> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM events WHERE chain_id = ?1 AND timestamp < ?2",
+                params![chain_id, before.timestamp_millis()],
+            )?;
+            Ok(deleted as u64)
+        })
+        .await
+    }
+
+    async fn max_block_number(&self, chain_id: &ID) -> Result<Option<u64>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            let max: Option<i64> = conn.query_row(
+                "SELECT MAX(block_number) FROM events WHERE chain_id = ?1",
+                params![chain_id],
+                |row| row.get(0),
+            )?;
+            Ok(max.map(|b| b as u64))
+        })
+        .await
+    }
+}
+
+#[async_trait::async_trait]
+impl ChainStore for SqliteStore {
+    async fn get_chain_info(&self, id: &ID) -> Result<Option<ChainInfo>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM chain_infos WHERE id = ?1", CHAIN_INFO_COLUMNS),
+                params![id],
+                row_to_chain_info,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn get_chain_infos_batch(&self, ids: &[ID]) -> Result<HashMap<ID, ChainInfo>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        self.with_conn(move |conn| {
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            let sql = format!(
+                "SELECT {} FROM chain_infos WHERE id IN ({})",
+                CHAIN_INFO_COLUMNS, placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(ids.iter()), row_to_chain_info)?;
+            rows.map(|row| row.map(|info| (info.id.clone(), info))).collect()
+        })
+        .await
+    }
+
+    async fn upsert_chain_info(&self, chain_info: ChainInfo) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO chain_infos ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    CHAIN_INFO_COLUMNS
+                ),
+                params![
+                    chain_info.id.as_str(),
+                    chain_info.name,
+                    chain_info.version,
+                    chain_info.token_symbol,
+                    chain_info.decimals,
+                    chain_info.ssv58_prefix,
+                    chain_info.last_updated.timestamp_millis(),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn save_runtime_metadata(&self, chain_id: &ID, metadata: RawMetadata) -> Result<()> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO runtime_metadata (chain_id, spec_version, metadata) VALUES (?1, ?2, ?3)",
+                params![chain_id, metadata.spec_version, metadata.bytes],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_runtime_metadata(&self, chain_id: &ID) -> Result<Option<RawMetadata>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT spec_version, metadata FROM runtime_metadata WHERE chain_id = ?1 ORDER BY spec_version DESC LIMIT 1",
+                params![chain_id],
+                |row| {
+                    Ok(RawMetadata {
+                        spec_version: row.get(0)?,
+                        bytes: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn list_registered_chains(&self) -> Result<Vec<ChainConfig>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT config FROM registered_chains ORDER BY registered_at, id")?;
+            let rows = stmt.query_map([], |row| {
+                let config: String = row.get(0)?;
+                serde_json::from_str(&config)
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e)))
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn save_registered_chain(&self, chain: ChainConfig) -> Result<()> {
+        let config = serde_json::to_string(&chain)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize chain config: {}", e)))?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO registered_chains (id, config, registered_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET config = excluded.config",
+                params![chain.id, config, Utc::now().timestamp_millis()],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete_registered_chain(&self, id: &ID) -> Result<()> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            conn.execute("DELETE FROM registered_chains WHERE id = ?1", params![id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.with_conn(|conn| conn.query_row("SELECT 1", [], |_| Ok(()))).await
+    }
+}
+
+#[async_trait::async_trait]
+impl BlockStore for SqliteStore {
+    async fn get_block_by_number(&self, chain_id: &ID, number: u64) -> Result<Option<Block>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM blocks WHERE chain_id = ?1 AND number = ?2", BLOCK_COLUMNS),
+                params![chain_id, number as i64],
+                row_to_block,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn get_block_by_hash(&self, chain_id: &ID, hash: &str) -> Result<Option<Block>> {
+        let chain_id = chain_id.to_string();
+        let hash = hash.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM blocks WHERE chain_id = ?1 AND hash = ?2", BLOCK_COLUMNS),
+                params![chain_id, hash],
+                row_to_block,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn get_blocks_batch(&self, chain_id: &ID, numbers: &[u64]) -> Result<HashMap<u64, Block>> {
+        if numbers.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut params: SqlParams = vec![Box::new(chain_id.to_string())];
+        params.extend(numbers.iter().map(|n| Box::new(*n as i64) as Box<dyn ToSql + Send>));
+        let placeholders = vec!["?"; numbers.len()].join(", ");
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM blocks WHERE chain_id = ? AND number IN ({})",
+                BLOCK_COLUMNS, placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), row_to_block)?;
+            rows.map(|row| row.map(|block| (block.number, block))).collect()
+        })
+        .await
+    }
+
+    async fn list_blocks(&self, chain_id: &ID, finalized_only: bool, page: &BlockPage) -> Result<Vec<Block>> {
+        let mut where_clause = "WHERE chain_id = ?".to_string();
+        let mut params: SqlParams = vec![Box::new(chain_id.to_string())];
+        if finalized_only {
+            where_clause.push_str(" AND finalized = 1");
+        }
+        if let Some(after) = page.after {
+            where_clause.push_str(" AND number < ?");
+            params.push(Box::new(after as i64));
+        }
+        if let Some(before) = page.before {
+            where_clause.push_str(" AND number > ?");
+            params.push(Box::new(before as i64));
+        }
+        params.push(Box::new(page.limit as i64));
+        let order = if page.from_oldest { "ASC" } else { "DESC" };
+        let from_oldest = page.from_oldest;
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM blocks {} ORDER BY number {} LIMIT ?",
+                BLOCK_COLUMNS, where_clause, order
+            );
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), row_to_block)?;
+            let mut blocks = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+            if from_oldest {
+                blocks.reverse();
+            }
+            Ok(blocks)
+        })
+        .await
+    }
+
+    async fn upsert_block(&self, block: Block) -> Result<()> {
+        self.with_conn(move |conn| write_block(conn, &block)).await
+    }
+
+    async fn indexed_block_height(&self, chain_id: &ID) -> Result<Option<u64>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            let max: Option<i64> = conn.query_row(
+                "SELECT MAX(number) FROM blocks WHERE chain_id = ?1",
+                params![chain_id],
+                |row| row.get(0),
+            )?;
+            Ok(max.map(|n| n as u64))
+        })
+        .await
+    }
+
+    async fn finalized_block_height(&self, chain_id: &ID) -> Result<Option<u64>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            let max: Option<i64> = conn.query_row(
+                "SELECT MAX(number) FROM blocks WHERE chain_id = ?1 AND finalized = 1",
+                params![chain_id],
+                |row| row.get(0),
+            )?;
+            Ok(max.map(|n| n as u64))
+        })
+        .await
+    }
+
+    async fn finalize_blocks(&self, chain_id: &ID, number: u64) -> Result<Vec<u64>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let numbers = {
+                let mut stmt = tx.prepare(
+                    "SELECT number FROM blocks WHERE chain_id = ?1 AND number <= ?2 AND finalized = 0 ORDER BY number",
+                )?;
+                let rows = stmt.query_map(params![chain_id, number as i64], |row| row.get::<_, i64>(0))?;
+                rows.map(|n| n.map(|n| n as u64)).collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            for table in ["events", "extrinsics"] {
+                tx.execute(
+                    &format!("UPDATE {} SET finalized = 1 WHERE chain_id = ?1 AND block_number <= ?2 AND finalized = 0", table),
+                    params![chain_id, number as i64],
+                )?;
+            }
+            tx.execute(
+                "UPDATE blocks SET finalized = 1 WHERE chain_id = ?1 AND number <= ?2 AND finalized = 0",
+                params![chain_id, number as i64],
+            )?;
+            tx.commit()?;
+            Ok(numbers)
+        })
+        .await
+    }
+
+    async fn rollback_blocks(&self, chain_id: &ID, number: u64) -> Result<Vec<Block>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let blocks = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT {} FROM blocks WHERE chain_id = ?1 AND number >= ?2 ORDER BY number",
+                    BLOCK_COLUMNS
+                ))?;
+                let rows = stmt.query_map(params![chain_id, number as i64], row_to_block)?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            for table in ["events", "extrinsics"] {
+                tx.execute(
+                    &format!("DELETE FROM {} WHERE chain_id = ?1 AND block_number >= ?2", table),
+                    params![chain_id, number as i64],
+                )?;
+            }
+            tx.execute(
+                "DELETE FROM blocks WHERE chain_id = ?1 AND number >= ?2",
+                params![chain_id, number as i64],
+            )?;
+            tx.commit()?;
+            Ok(blocks)
+        })
+        .await
+    }
+}
+
+#[async_trait::async_trait]
+impl ExtrinsicStore for SqliteStore {
+    async fn get_extrinsic(&self, chain_id: &ID, id: &ID) -> Result<Option<Extrinsic>> {
+        let chain_id = chain_id.to_string();
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM extrinsics WHERE chain_id = ?1 AND id = ?2", EXTRINSIC_COLUMNS),
+                params![chain_id, id],
+                row_to_extrinsic,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn get_extrinsics_batch(&self, chain_id: &ID, ids: &[ID]) -> Result<HashMap<ID, Extrinsic>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut params: SqlParams = vec![Box::new(chain_id.to_string())];
+        params.extend(ids.iter().map(|id| Box::new(id.to_string()) as Box<dyn ToSql + Send>));
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM extrinsics WHERE chain_id = ? AND id IN ({})",
+                EXTRINSIC_COLUMNS, placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), row_to_extrinsic)?;
+            rows.map(|row| row.map(|extrinsic| (extrinsic.id.clone(), extrinsic))).collect()
+        })
+        .await
+    }
+
+    async fn list_extrinsics(
+        &self,
+        chain_id: &ID,
+        filter: Option<&ExtrinsicFilterInput>,
+        page: &ExtrinsicPage,
+    ) -> Result<Vec<Extrinsic>> {
+        let (mut where_clause, mut params) = extrinsic_filter_to_sql(chain_id, filter);
+        if let Some(after) = page.after {
+            where_clause.push_str(" AND (block_number, extrinsic_index) < (?, ?)");
+            params.push(Box::new(after.block_number as i64));
+            params.push(Box::new(after.index));
+        }
+        if let Some(before) = page.before {
+            where_clause.push_str(" AND (block_number, extrinsic_index) > (?, ?)");
+            params.push(Box::new(before.block_number as i64));
+            params.push(Box::new(before.index));
+        }
+        params.push(Box::new(page.limit as i64));
+        let order = if page.from_oldest { "ASC" } else { "DESC" };
+        let from_oldest = page.from_oldest;
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM extrinsics {} ORDER BY block_number {order}, extrinsic_index {order} LIMIT ?",
+                EXTRINSIC_COLUMNS, where_clause
+            );
+            let mut stmt = conn.prepare_cached(&sql)?;
+            let rows = stmt.query_map(params_from_iter(params.iter().map(|p| p.as_ref())), row_to_extrinsic)?;
+            let mut extrinsics = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+            if from_oldest {
+                extrinsics.reverse();
+            }
+            Ok(extrinsics)
+        })
+        .await
+    }
+
+    async fn insert_extrinsic(&self, extrinsic: Extrinsic) -> Result<()> {
+        self.with_conn(move |conn| write_extrinsic(conn, &extrinsic)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl AccountStore for SqliteStore {
+    async fn account_activity(&self, chain_id: &ID, account: &str) -> Result<Option<(u64, u64)>> {
+        let chain_id = chain_id.to_string();
+        let account = account.to_string();
+        self.with_conn(move |conn| {
+            let (first, last): (Option<i64>, Option<i64>) = conn.query_row(
+                "SELECT MIN(block_number), MAX(block_number) FROM (
+                     SELECT e.block_number FROM event_accounts a JOIN events e ON e.id = a.event_id
+                     WHERE a.account = ?2 AND e.chain_id = ?1
+                     UNION ALL
+                     SELECT block_number FROM extrinsics WHERE chain_id = ?1 AND signer = ?2
+                 )",
+                params![chain_id, account],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            Ok(first.zip(last).map(|(first, last)| (first as u64, last as u64)))
+        })
+        .await
+    }
+}
+
+#[async_trait::async_trait]
+impl BackfillStore for SqliteStore {
+    async fn get_backfill(&self, chain_id: &ID) -> Result<Option<BackfillStatus>> {
+        let chain_id = chain_id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM backfills WHERE chain_id = ?1", BACKFILL_COLUMNS),
+                params![chain_id],
+                row_to_backfill,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn save_backfill(&self, status: BackfillStatus) -> Result<()> {
+        self.with_conn(move |conn| write_backfill(conn, &status)).await
+    }
+
+    async fn save_backfill_batch(&self, blocks: Vec<IndexedBlock>, status: BackfillStatus) -> Result<()> {
+        self.with_conn(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            for indexed in &blocks {
+                write_block(&tx, &indexed.block)?;
+                for extrinsic in &indexed.extrinsics {
+                    write_extrinsic(&tx, extrinsic)?;
+                }
+                for event in &indexed.events {
+                    write_event(&tx, event)?;
+                }
+            }
+            write_backfill(&tx, &status)?;
+            tx.commit()
+        })
+        .await
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for SqliteStore {
+    async fn list_api_keys(&self) -> Result<Vec<StoredApiKey>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM api_keys ORDER BY created_at", API_KEY_COLUMNS))?;
+            let rows = stmt.query_map([], row_to_api_key)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_api_key_by_hash(&self, key_hash: &str) -> Result<Option<StoredApiKey>> {
+        let key_hash = key_hash.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM api_keys WHERE key_hash = ?1", API_KEY_COLUMNS),
+                params![key_hash],
+                row_to_api_key,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn has_api_key_named(&self, name: &str) -> Result<bool> {
+        let name = name.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row("SELECT EXISTS (SELECT 1 FROM api_keys WHERE name = ?1)", params![name], |row| row.get(0))
+        })
+        .await
+    }
+
+    async fn rotate_api_key(&self, key: StoredApiKey) -> Result<()> {
+        let scopes = key.scopes.iter().map(Scope::as_str).collect::<Vec<_>>().join(" ");
+        self.with_conn(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute("DELETE FROM api_keys WHERE name = ?1", params![key.name])?;
+            tx.execute(
+                "INSERT INTO api_keys (id, name, key_hash, scopes, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![key.id.as_str(), key.name, key.key_hash, scopes, key.created_at.timestamp_millis()],
+            )?;
+            tx.commit()
+        })
+        .await
+    }
+}
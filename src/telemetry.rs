@@ -0,0 +1,142 @@
+use crate::config::TelemetryConfig;
+use crate::errors::{AppError, Result};
+use actix_web::http::header::HeaderMap;
+use actix_web::HttpRequest;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, Tracer, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::{Span, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+// Layer exporting the spans to the configured OTLP collector, `None` when there is none.
+// Installs the W3C trace context propagator along with it.
+pub fn layer<S>(config: &TelemetryConfig) -> Result<Option<OpenTelemetryLayer<S, Tracer>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let Some(endpoint) = &config.otlp_endpoint else {
+        return Ok(None);
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to create OTLP exporter for {}: {}", endpoint, e)))?;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        // Callers that sent a `traceparent` already decided whether the trace is sampled
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sampling_ratio))))
+        .with_resource(Resource::new(vec![KeyValue::new("service.name", config.service_name.clone())]))
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    global::set_tracer_provider(provider);
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+// Export the spans still buffered. Blocks, so it runs off the async runtime.
+pub async fn shutdown() {
+    let _ = tokio::task::spawn_blocking(global::shutdown_tracer_provider).await;
+}
+
+// Root span of one GraphQL request, continuing the caller's trace when the request
+// carries a `traceparent` header. The resolver spans nest under it.
+pub fn request_span(req: &HttpRequest) -> Span {
+    let span = tracing::info_span!("graphql.request", otel.kind = "server");
+    let remote = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(req.headers())));
+    span.set_parent(remote);
+    span
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, ChainConfig};
+    use crate::indexer::SubstrateIndexerService;
+    use crate::models::{AuthMethod, Caller, Scope};
+    use crate::schema::{MutationRoot, QueryRoot, SubscriptionRoot};
+    use crate::store::MemoryStore;
+    use async_graphql::{extensions, Request, Schema};
+    use opentelemetry::trace::{SpanId, TraceId};
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tracing::Instrument;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const CALLER_SPAN_ID: &str = "00f067aa0ba902b7";
+
+    #[tokio::test]
+    async fn request_span_continues_the_callers_trace() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _subscriber = tracing::subscriber::set_default(subscriber);
+
+        let chain = ChainConfig {
+            id: "dev".to_string(),
+            name: "Dev".to_string(),
+            endpoint: "ws://127.0.0.1:9944".to_string(),
+            replay_file: None,
+            backfill_from_block: None,
+            ss58_prefix: 42,
+            token_symbol: "UNIT".to_string(),
+            decimals: 12,
+        };
+        let service = SubstrateIndexerService::new(AppConfig::for_chains(vec![chain]), Arc::new(MemoryStore::default()));
+        service.register_chains().await.unwrap();
+        let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+            .data(service)
+            .extension(extensions::Tracing)
+            .finish();
+        let caller = Caller {
+            subject: "anonymous".to_string(),
+            method: AuthMethod::Anonymous,
+            scopes: vec![Scope::Read],
+        };
+        exporter.reset(); // Spans of the setup above
+
+        let http_req = actix_web::test::TestRequest::default()
+            .insert_header(("traceparent", format!("00-{}-{}-01", TRACE_ID, CALLER_SPAN_ID)))
+            .to_http_request();
+        let response = schema
+            .execute(Request::new("{ chains { id name } }").data(caller))
+            .instrument(request_span(&http_req))
+            .await;
+        assert!(response.is_ok(), "{:?}", response.errors);
+        provider.force_flush();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let request = spans.iter().find(|s| s.name == "graphql.request").expect("no graphql.request span");
+        assert_eq!(request.span_context.trace_id(), TraceId::from_hex(TRACE_ID).unwrap());
+        assert_eq!(request.parent_span_id, SpanId::from_hex(CALLER_SPAN_ID).unwrap());
+
+        // Every span of the request leads up to it
+        let parents: HashMap<SpanId, SpanId> = spans.iter().map(|s| (s.span_context.span_id(), s.parent_span_id)).collect();
+        let resolver = spans.iter().find(|s| s.name == "query.chains").expect("no resolver span");
+        let mut ancestor = resolver.parent_span_id;
+        while ancestor != request.span_context.span_id() {
+            ancestor = *parents.get(&ancestor).expect("resolver span is not nested under graphql.request");
+        }
+        assert!(spans.iter().all(|s| s.span_context.trace_id() == request.span_context.trace_id()));
+    }
+}
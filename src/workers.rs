@@ -0,0 +1,174 @@
+use crate::config::AppConfig;
+use crate::errors::{AppError, Result};
+use crate::indexer::SubstrateIndexerService;
+use crate::ingest::{Endpoints, Ingestor};
+use crate::models::{WorkerKind, WorkerState, WorkerStatus};
+use async_graphql::ID;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+struct Worker {
+    kind: WorkerKind,
+    control: watch::Sender<WorkerState>,
+    task: JoinHandle<()>,
+}
+
+// Starts, pauses and stops the task feeding each chain, node ingestion or the
+// simulator. A chain has at most one, switching kinds means stopping it first.
+#[derive(Clone)]
+pub struct Workers {
+    service: SubstrateIndexerService,
+    config: Arc<AppConfig>,
+    endpoints: Endpoints,
+    workers: Arc<Mutex<HashMap<ID, Worker>>>,
+}
+
+impl Workers {
+    pub fn new(service: SubstrateIndexerService, config: AppConfig, endpoints: Endpoints) -> Self {
+        Self {
+            service,
+            config: Arc::new(config),
+            endpoints,
+            workers: Arc::default(),
+        }
+    }
+
+    // Ingestion when it is enabled in the configuration, the simulator otherwise
+    pub fn default_kind(&self) -> WorkerKind {
+        if self.config.ingestion.enabled {
+            WorkerKind::Ingestion
+        } else {
+            WorkerKind::Simulator
+        }
+    }
+
+    pub async fn status(&self, chain_id: &ID) -> Option<WorkerStatus> {
+        let workers = self.workers.lock().await;
+        workers.get(chain_id).map(|worker| WorkerStatus {
+            chain_id: chain_id.clone(),
+            kind: worker.kind,
+            state: *worker.control.borrow(),
+        })
+    }
+
+    // Whether the chain's worker task is still there, `None` when the chain has none.
+    // Tasks only end when stopped, one that ended by itself failed.
+    pub async fn is_alive(&self, chain_id: &ID) -> Option<bool> {
+        let workers = self.workers.lock().await;
+        workers.get(chain_id).map(|worker| !worker.task.is_finished())
+    }
+
+    // Start a `kind` worker for the chain, or resume it when paused.
+    pub async fn start(&self, chain_id: &ID, kind: WorkerKind) -> Result<WorkerStatus> {
+        let chain = self.service.chain(chain_id)?;
+        let mut workers = self.workers.lock().await;
+        if let Some(worker) = workers.get(chain_id) {
+            if worker.kind != kind {
+                return Err(AppError::InvalidInput(format!(
+                    "Chain {} is fed by {:?}, stop it first",
+                    chain_id.as_str(),
+                    worker.kind
+                )));
+            }
+            worker.control.send_replace(WorkerState::Running);
+            info!(chain_id = %chain_id.as_str(), ?kind, "Resumed worker");
+        } else {
+            let (control, receiver) = watch::channel(WorkerState::Running);
+            let task = match kind {
+                WorkerKind::Ingestion => {
+                    let endpoint = self.endpoints.resolve(&chain).await?;
+                    Ingestor::spawn(self.service.clone(), chain_id.clone(), endpoint, self.config.ingestion.clone(), receiver)
+                }
+                WorkerKind::Simulator => tokio::spawn(simulate(
+                    self.service.clone(),
+                    chain_id.clone(),
+                    self.config.mock_event_min_delay_secs..=self.config.mock_event_max_delay_secs,
+                    receiver,
+                )),
+            };
+            workers.insert(chain_id.clone(), Worker { kind, control, task });
+            info!(chain_id = %chain_id.as_str(), ?kind, "Started worker");
+        }
+        Ok(WorkerStatus {
+            chain_id: chain_id.clone(),
+            kind,
+            state: WorkerState::Running,
+        })
+    }
+
+    pub async fn pause(&self, chain_id: &ID) -> Result<WorkerStatus> {
+        let workers = self.workers.lock().await;
+        let worker = workers.get(chain_id).ok_or_else(|| no_worker(chain_id))?;
+        worker.control.send_replace(WorkerState::Paused);
+        info!(chain_id = %chain_id.as_str(), kind = ?worker.kind, "Paused worker");
+        Ok(WorkerStatus {
+            chain_id: chain_id.clone(),
+            kind: worker.kind,
+            state: WorkerState::Paused,
+        })
+    }
+
+    // Stop the chain's worker, waiting for it to finish the block at hand.
+    pub async fn stop(&self, chain_id: &ID) -> Result<WorkerStatus> {
+        let mut workers = self.workers.lock().await;
+        let worker = workers.remove(chain_id).ok_or_else(|| no_worker(chain_id))?;
+        worker.control.send_replace(WorkerState::Stopped);
+        if let Err(e) = worker.task.await {
+            error!(chain_id = %chain_id.as_str(), "Worker task failed: {}", e);
+        }
+        info!(chain_id = %chain_id.as_str(), kind = ?worker.kind, "Stopped worker");
+        Ok(WorkerStatus {
+            chain_id: chain_id.clone(),
+            kind: worker.kind,
+            state: WorkerState::Stopped,
+        })
+    }
+
+    // Stop whatever feeds a chain that is being removed and forget its endpoint.
+    pub async fn retire(&self, chain_id: &ID) {
+        // Only fails when nothing feeds the chain
+        let _ = self.stop(chain_id).await;
+        self.endpoints.forget(chain_id).await;
+    }
+}
+
+fn no_worker(chain_id: &ID) -> AppError {
+    AppError::NotFound(format!("No worker for chain: {}", chain_id.as_str()))
+}
+
+// Wait until the worker may run. False once it is stopped.
+pub async fn wait_until_running(control: &mut watch::Receiver<WorkerState>) -> bool {
+    loop {
+        match *control.borrow_and_update() {
+            WorkerState::Running => return true,
+            WorkerState::Stopped => return false,
+            WorkerState::Paused => {}
+        }
+        if control.changed().await.is_err() {
+            return false;
+        }
+    }
+}
+
+// Simulate a block on the chain every `delay_secs`, picked at random each time.
+async fn simulate(
+    service: SubstrateIndexerService,
+    chain_id: ID,
+    delay_secs: std::ops::RangeInclusive<u64>,
+    mut control: watch::Receiver<WorkerState>,
+) {
+    info!(chain_id = %chain_id.as_str(), "Starting mock event simulation");
+    while wait_until_running(&mut control).await {
+        let delay = rand::thread_rng().gen_range(delay_secs.clone());
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(delay)) => service.simulate_step(&chain_id).await,
+            _ = control.changed() => {}
+        }
+    }
+    info!(chain_id = %chain_id.as_str(), "Mock event simulation stopped");
+}